@@ -0,0 +1,142 @@
+use quote::ToTokens;
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+
+/// A change to the attributes of a field, written as `#[+serde(skip)]` or `#[-serde]`.
+///
+/// Changes to the attributes of the derived type are written in front of the attribute instead,
+/// e.g. `+#[derive(Hash)]` or `-#[derive(Copy)]`.
+pub enum AttributeChange {
+    /// Add an attribute, indicated by a `+` prefix.
+    Add(syn::Attribute),
+    /// Remove every attribute with the path, indicated by a `-` prefix.
+    /// With a list like `derive(Copy)` only the listed entries are removed.
+    Remove(syn::Meta),
+}
+
+impl Parse for AttributeChange {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let content;
+        let pound_token = input.parse()?;
+        let bracket_token = syn::bracketed!(content in input);
+
+        if content.peek(syn::Token![+]) {
+            content.parse::<syn::Token![+]>()?;
+            return Ok(AttributeChange::Add(syn::Attribute {
+                pound_token,
+                style: syn::AttrStyle::Outer,
+                bracket_token,
+                meta: content.parse()?,
+            }));
+        }
+
+        if content.peek(syn::Token![-]) {
+            content.parse::<syn::Token![-]>()?;
+            return Ok(AttributeChange::Remove(content.parse()?));
+        }
+
+        Err(content.error("expected `#[+attribute]` or `#[-path]`"))
+    }
+}
+
+impl AttributeChange {
+    /// Parses every change at the start of `input`.
+    pub fn parse_all(input: ParseStream) -> syn::Result<Vec<Self>> {
+        let mut changes = Vec::new();
+        while input.peek(syn::Token![#]) {
+            changes.push(input.parse()?);
+        }
+
+        Ok(changes)
+    }
+
+    /// Parses the attributes of a derived type, where plain attributes are added as well.
+    pub fn parse_container(input: ParseStream) -> syn::Result<Vec<Self>> {
+        let mut changes = Vec::new();
+        loop {
+            if input.peek(syn::Token![#]) {
+                changes.extend(input.call(syn::Attribute::parse_outer)?.into_iter().map(AttributeChange::Add));
+            } else if input.peek(syn::Token![+]) && input.peek2(syn::Token![#]) {
+                input.parse::<syn::Token![+]>()?;
+                changes.extend(input.call(syn::Attribute::parse_outer)?.into_iter().map(AttributeChange::Add));
+            } else if input.peek(syn::Token![-]) && input.peek2(syn::Token![#]) {
+                input.parse::<syn::Token![-]>()?;
+                for attr in input.call(syn::Attribute::parse_outer)? {
+                    changes.push(AttributeChange::Remove(attr.meta));
+                }
+            } else {
+                return Ok(changes);
+            }
+        }
+    }
+
+    /// The attributes of `source` a derived type inherits, which are all but the `mapstruct` ones.
+    /// The compiler hands a derive only the attributes after its own `#[derive(...)]`, so derives listed
+    /// next to `MapStruct` or in front of it are never inherited.
+    pub fn inherited(source: &[syn::Attribute]) -> Vec<syn::Attribute> {
+        source.iter()
+            .filter(|attr| !attr.path().is_ident("mapstruct"))
+            .cloned()
+            .collect()
+    }
+
+    /// Applies the changes in order to `attrs`. Removing an attribute which is not present is an error.
+    pub fn apply(changes: &[AttributeChange], attrs: &mut Vec<syn::Attribute>) -> syn::Result<()> {
+        for change in changes {
+            match change {
+                AttributeChange::Add(attr) => attrs.push(attr.clone()),
+                AttributeChange::Remove(syn::Meta::List(list)) => {
+                    let removals = list.parse_args_with(Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated)?;
+                    let mut removed = vec![false; removals.len()];
+                    for attr in attrs.iter_mut().filter(|attr| same(attr.path(), &list.path)) {
+                        let mut entries = list_entries(attr)?;
+                        entries.retain(|entry| {
+                            let index = removals.iter().position(|removal| same(removal, entry));
+                            if let Some(index) = index {
+                                removed[index] = true;
+                            }
+                            index.is_none()
+                        });
+                        set_list_entries(attr, entries);
+                    }
+                    attrs.retain(|attr| !matches!(&attr.meta, syn::Meta::List(emptied) if same(&emptied.path, &list.path) && emptied.tokens.is_empty()));
+
+                    if let Some(index) = removed.iter().position(|removed| !removed) {
+                        return Err(syn::Error::new_spanned(&removals[index], "No attribute with this entry"));
+                    }
+                },
+                AttributeChange::Remove(meta) => {
+                    let len = attrs.len();
+                    attrs.retain(|attr| !same(attr.path(), meta.path()));
+                    if attrs.len() == len {
+                        return Err(syn::Error::new_spanned(meta, "No attribute with this path"));
+                    }
+                },
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The comma separated entries of an attribute like `#[derive(Debug, Clone)]`.
+fn list_entries(attr: &syn::Attribute) -> syn::Result<Vec<syn::Meta>> {
+    match &attr.meta {
+        syn::Meta::List(list) => {
+            let entries = list.parse_args_with(Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated)?;
+            Ok(entries.into_iter().collect())
+        },
+        _ => Err(syn::Error::new_spanned(attr, "expected a list like `#[derive(...)]`")),
+    }
+}
+
+fn set_list_entries(attr: &mut syn::Attribute, entries: Vec<syn::Meta>) {
+    if let syn::Meta::List(list) = &mut attr.meta {
+        let entries = entries.into_iter().collect::<Punctuated<_, syn::Token![,]>>();
+        list.tokens = entries.into_token_stream();
+    }
+}
+
+fn same(a: &impl ToTokens, b: &impl ToTokens) -> bool {
+    a.to_token_stream().to_string() == b.to_token_stream().to_string()
+}
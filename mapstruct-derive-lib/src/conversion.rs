@@ -0,0 +1,659 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, ToTokens};
+use syn::{DeriveInput, GenericParam};
+
+use crate::converter::Converter;
+use crate::generic::{self, param_ident, Substitution};
+use crate::inference::Inference;
+
+/// Describes how a field of the derived type is produced.
+#[derive(Clone)]
+pub enum FieldValue {
+    /// The field is taken over from the source, possibly renamed.
+    Moved,
+    /// The field was retyped by the change at the given span, converted by `with` if given.
+    Retyped {
+        span: Span,
+        with: Option<Box<Converter>>,
+    },
+    /// The field was added by the change at the given span, computed by `init` if given.
+    Added {
+        span: Span,
+        init: Option<syn::Expr>,
+    },
+    /// The field was removed by the change at the given span. `default` restores it
+    /// in reverse conversions.
+    Removed {
+        span: Span,
+        default: Option<syn::Expr>,
+    },
+}
+
+/// Links a field of the source to a field of the derived type.
+pub struct FieldMapping {
+    /// The position and field in the source, `None` for added fields.
+    pub source: Option<(usize, syn::Field)>,
+    /// The field in the derived type, `None` for removed fields.
+    pub target: Option<syn::Field>,
+    /// How the target is produced from the source.
+    pub value: FieldValue,
+}
+
+/// Describes how a variant of the derived enum is produced.
+pub enum VariantValue {
+    /// The variant is taken over from the source, its fields are related by the mappings.
+    Mapped(Vec<FieldMapping>),
+    /// The variant was replaced by the change at the given span and has no relation to the source.
+    Replaced {
+        span: Span,
+    },
+    /// The variant was added by the change at the given span.
+    Added {
+        span: Span,
+    },
+    /// The variant was removed by the change at the given span.
+    Removed {
+        span: Span,
+    },
+    /// The hidden variant keeping unused generic parameters with `phantom`, which can not be constructed.
+    Phantom,
+}
+
+/// Links a variant of the source to a variant of the derived enum.
+pub struct VariantMapping {
+    /// The variant in the source, `None` for added variants.
+    pub source: Option<syn::Variant>,
+    /// The variant in the derived enum, `None` for removed variants.
+    pub target: Option<syn::Variant>,
+    /// How the target is produced from the source.
+    pub value: VariantValue,
+}
+
+/// Maps every field onto itself, for variants which keep their fields.
+pub fn unchanged(fields: &syn::Fields) -> Vec<FieldMapping> {
+    fields.iter()
+        .cloned()
+        .enumerate()
+        .map(|(index, field)| FieldMapping {
+            source: Some((index, field.clone())),
+            target: Some(field),
+            value: FieldValue::Moved,
+        })
+        .collect()
+}
+
+/// Settings shared by every field of a generated conversion.
+#[derive(Clone, Copy, Default)]
+pub struct Options<'a> {
+    /// The error enum of a `TryFrom` conversion. Fallible fields are converted
+    /// with `?` into its variants.
+    pub error: Option<&'a syn::Ident>,
+    /// The lifetime the source is borrowed for, if it is not consumed.
+    pub borrow: Option<&'a syn::Lifetime>,
+    /// The parameters of the type derived from which the derived type substitutes, e.g. `~T => String`.
+    /// The type derived from is named with their values, `X<String>`, and the impl does not declare them.
+    pub substitutions: &'a [Substitution],
+    /// The newtypes declared with `newtype Id(i64)`, which are unwrapped with `.0`.
+    pub newtypes: &'a [(&'a syn::Path, &'a syn::Type)],
+}
+
+impl Options<'_> {
+    /// Takes the field at `place` out of the source, cloning it if the source is borrowed.
+    fn value(&self, place: TokenStream) -> TokenStream {
+        if self.borrow.is_some() {
+            quote! { ::core::clone::Clone::clone(&#place) }
+        } else {
+            place
+        }
+    }
+}
+
+/// The identifier the source value is bound to inside of generated conversions.
+pub fn source_binding() -> syn::Ident {
+    syn::Ident::new("src", Span::call_site())
+}
+
+/// Builds the value of the derived type at `path`, e.g. `Self` or `Self::A`, shaped like `fields`.
+/// The fields of the source are accessed through `place`.
+///
+/// Added fields are computed up front, so their expressions can still borrow
+/// every field of the source before it is moved.
+pub fn construct(
+    path: TokenStream,
+    fields: &syn::Fields,
+    mappings: &[FieldMapping],
+    place: impl Fn(usize, &syn::Field) -> TokenStream,
+    options: Options,
+) -> syn::Result<TokenStream> {
+    let computed = targets(mappings)
+        .filter_map(|(name, mapping, _)| match &mapping.value {
+            FieldValue::Added { init: Some(init), .. } => {
+                let local = local_binding(&name);
+                Some(quote! { let #local = #init; })
+            },
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let values = targets(mappings)
+        .map(|(name, mapping, _)| match (&mapping.value, &mapping.source) {
+            (FieldValue::Moved, Some((index, source))) => Ok(options.value(place(*index, source))),
+            (FieldValue::Retyped { .. }, Some((index, source))) => {
+                retyped_value(&name, mapping, place(*index, source), options)
+            },
+            (FieldValue::Added { init: Some(_), .. }, _) => {
+                let local = local_binding(&name);
+                Ok(quote! { #local })
+            },
+            (FieldValue::Added { span, init: None }, _) => Err(syn::Error::new(
+                *span,
+                format!("cannot generate a conversion, field `{}` is added without a way to produce it", name),
+            )),
+            _ => unreachable!(),
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let value = match fields {
+        syn::Fields::Named(_) => {
+            let idents = targets(mappings).map(|(_, _, target)| target.ident.as_ref().unwrap());
+            quote! {
+                #path {
+                    #(#idents: #values),*
+                }
+            }
+        },
+        syn::Fields::Unnamed(_) => quote! { #path(#(#values),*) },
+        syn::Fields::Unit => path,
+    };
+
+    Ok(quote! {
+        #(#computed)*
+        #value
+    })
+}
+
+/// Builds `match src { ... }`, converting every variant of the source enum `source` into its counterpart.
+///
+/// Removed variants can only be converted if the conversion is `fallible`, they are returned as error then.
+pub fn match_variants(source: &syn::Ident, mappings: &[VariantMapping], options: Options, fallible: bool) -> syn::Result<TokenStream> {
+    let src = source_binding();
+    let arms = mappings.iter()
+        .filter_map(|mapping| mapping.source.as_ref().map(|variant| (mapping, variant)))
+        .map(|(mapping, variant)| {
+            let ident = &variant.ident;
+            match (&mapping.value, &mapping.target) {
+                (VariantValue::Mapped(fields), Some(target)) => {
+                    let pattern = variant_pattern(source, variant, fields);
+                    let target_ident = &target.ident;
+                    let place = |index: usize, field: &syn::Field| {
+                        let binding = variant_binding(index, field);
+                        if options.borrow.is_some() {
+                            quote! { (*#binding) }
+                        } else {
+                            quote! { #binding }
+                        }
+                    };
+                    let value = construct(quote! { Self::#target_ident }, &target.fields, fields, place, options)?;
+                    Ok(quote! { #pattern => { #value } })
+                },
+                (VariantValue::Phantom, _) => Ok(quote! { #source::#ident(never, _) => match never {} }),
+                (VariantValue::Removed { .. }, _) if fallible => {
+                    Ok(quote! { #source::#ident { .. } => return ::core::result::Result::Err(#src) })
+                },
+                (VariantValue::Removed { span }, _) => Err(syn::Error::new(
+                    *span,
+                    format!("cannot generate a conversion, variant `{}` is removed, use `try_from`", ident),
+                )),
+                (VariantValue::Replaced { span }, _) => Err(syn::Error::new(
+                    *span,
+                    format!("cannot generate a conversion, variant `{}` is replaced", ident),
+                )),
+                _ => unreachable!(),
+            }
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    Ok(quote! {
+        match #src {
+            #(#arms,)*
+        }
+    })
+}
+
+/// Inverts the variant mappings, so they describe how to get from the derived enum back to the source.
+pub fn reverse_variants(mappings: &[VariantMapping]) -> syn::Result<Vec<VariantMapping>> {
+    mappings.iter()
+        .map(|mapping| {
+            let value = match &mapping.value {
+                VariantValue::Mapped(fields) => VariantValue::Mapped(reverse(fields)?),
+                VariantValue::Replaced { span } => VariantValue::Replaced { span: *span },
+                VariantValue::Added { span } => return Err(syn::Error::new(
+                    *span,
+                    format!(
+                        "cannot generate a reverse conversion, variant `{}` is added",
+                        mapping.target.as_ref().unwrap().ident,
+                    ),
+                )),
+                VariantValue::Removed { span } => VariantValue::Added { span: *span },
+                VariantValue::Phantom => VariantValue::Phantom,
+            };
+
+            Ok(VariantMapping {
+                source: mapping.target.clone(),
+                target: mapping.source.clone(),
+                value,
+            })
+        })
+        .collect()
+}
+
+/// The pattern of `variant`, binding every field which is used by the mappings.
+/// Struct variants bind their removed fields as well, so initializers can read them by name,
+/// and allow them to stay unused.
+fn variant_pattern(source: &syn::Ident, variant: &syn::Variant, mappings: &[FieldMapping]) -> TokenStream {
+    let ident = &variant.ident;
+    let used = |index: usize| mappings.iter()
+        .any(|mapping| matches!(&mapping.source, Some((source, _)) if *source == index)
+            && !matches!(mapping.value, FieldValue::Removed { .. }));
+
+    match &variant.fields {
+        syn::Fields::Named(fields) => {
+            let bindings = fields.named.iter().map(|field| field.ident.as_ref().unwrap());
+            let allow = (0..fields.named.len()).any(|index| !used(index))
+                .then(|| quote! { #[allow(unused_variables)] });
+            quote! { #allow #source::#ident { #(#bindings,)* } }
+        },
+        syn::Fields::Unnamed(fields) => {
+            let bindings = fields.unnamed.iter()
+                .enumerate()
+                .map(|(index, field)| if used(index) {
+                    variant_binding(index, field)
+                } else {
+                    quote! { _ }
+                });
+            quote! { #source::#ident(#(#bindings),*) }
+        },
+        syn::Fields::Unit => quote! { #source::#ident },
+    }
+}
+
+/// The binding of a field in a variant pattern. Named fields keep their name, so
+/// initializers of added fields can read them.
+fn variant_binding(index: usize, field: &syn::Field) -> TokenStream {
+    match &field.ident {
+        Some(ident) => quote! { #ident },
+        None => {
+            let ident = quote::format_ident!("field{}", index, span = Span::mixed_site());
+            quote! { #ident }
+        },
+    }
+}
+
+/// Accesses a field of the source value bound to `src`.
+pub fn source_place(index: usize, field: &syn::Field) -> TokenStream {
+    let src = source_binding();
+    let member = member(index, field);
+    quote! { #src.#member }
+}
+
+fn member(index: usize, field: &syn::Field) -> syn::Member {
+    match &field.ident {
+        Some(ident) => syn::Member::Named(ident.clone()),
+        None => syn::Member::Unnamed(index.into()),
+    }
+}
+
+/// Converts the field at `place` of a retyped field into its new type.
+///
+/// Explicit converters win over inferred ones. `TryFrom` is the last resort of fallible conversions.
+fn retyped_value(name: &str, mapping: &FieldMapping, place: TokenStream, options: Options) -> syn::Result<TokenStream> {
+    let (span, with) = crate::unwrap_one_variant!(&mapping.value, FieldValue::Retyped { span, with }, (span, with));
+    let inference = inferred(mapping, options);
+    // Converters of a borrowed source receive a reference instead of the value.
+    let argument = if options.borrow.is_some() {
+        quote! { &#place }
+    } else {
+        place.clone()
+    };
+
+    match (with, inference, options.error) {
+        (Some(with), _, _) if !with.is_fallible() => Ok(with.call(argument)),
+        (Some(with), _, Some(error)) => {
+            let variant = error_variant(name);
+            let value = with.call(argument);
+            Ok(quote! { #value.map_err(#error::#variant)? })
+        },
+        (Some(_), _, None) => Err(syn::Error::new(
+            *span,
+            format!("cannot generate a conversion, field `{}` has a fallible conversion, use `try_from`", name),
+        )),
+        (None, Some(inference), _) if inference.borrows() && options.borrow.is_none() => Err(syn::Error::new(
+            *span,
+            format!("cannot generate a conversion, field `{}` borrows from the source, use `from(&'a)`", name),
+        )),
+        (None, Some(inference), _) => Ok(inference.apply(place, options.borrow.is_some())),
+        (None, None, Some(error)) => {
+            let variant = error_variant(name);
+            let value = options.value(place);
+            Ok(quote! { ::core::convert::TryFrom::try_from(#value).map_err(#error::#variant)? })
+        },
+        (None, None, None) => {
+            let (source, target) = (&mapping.source.as_ref().unwrap().1.ty, &mapping.target.as_ref().unwrap().ty);
+            Err(syn::Error::new(
+                *span,
+                format!(
+                    "cannot infer a conversion of field `{}` from `{}` to `{}`, provide one with `with`",
+                    name,
+                    source.to_token_stream(),
+                    target.to_token_stream(),
+                ),
+            ))
+        },
+    }
+}
+
+/// The conversion of a retyped field without a converter, if it is obvious.
+fn inferred(mapping: &FieldMapping, options: Options) -> Option<Inference> {
+    match (&mapping.source, &mapping.target) {
+        (Some((_, source)), Some(target)) => Inference::infer(
+            &generic::in_conversion(options.substitutions, &source.ty),
+            &generic::in_conversion(options.substitutions, &target.ty),
+            options.newtypes,
+        ),
+        _ => None,
+    }
+}
+
+/// The error type of a fallible field conversion, if there is one.
+fn fallible_type(mapping: &FieldMapping, options: Options) -> Option<syn::Type> {
+    match (&mapping.value, &mapping.source, &mapping.target) {
+        (FieldValue::Retyped { with: Some(with), .. }, _, _) => with.error().cloned(),
+        (FieldValue::Retyped { with: None, .. }, Some((_, source)), Some(target)) if inferred(mapping, options).is_none() => {
+            let source = generic::in_conversion(options.substitutions, &source.ty);
+            let target = generic::in_conversion(options.substitutions, &target.ty);
+            Some(syn::parse_quote! { <#target as ::core::convert::TryFrom<#source>>::Error })
+        },
+        _ => None,
+    }
+}
+
+/// The mappings which end up in the derived type, with the name of their field.
+fn targets(mappings: &[FieldMapping]) -> impl Iterator<Item = (String, &FieldMapping, &syn::Field)> {
+    mappings.iter()
+        .filter_map(|mapping| mapping.target.as_ref().map(|target| (mapping, target)))
+        .enumerate()
+        .map(|(index, (mapping, target))| (field_name(index, target), mapping, target))
+}
+
+/// Turns `created_at` into `CreatedAt`, the name of the error variant of a field.
+/// Tuple positions are called `Field0`, `Field1` and so on.
+fn error_variant(name: &str) -> syn::Ident {
+    if name.starts_with(|c: char| c.is_ascii_digit()) {
+        return quote::format_ident!("Field{}", name);
+    }
+
+    let name = name.trim_start_matches("r#")
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars.next().map(|first| first.to_uppercase().chain(chars).collect::<String>()).unwrap_or_default()
+        })
+        .collect::<String>();
+
+    syn::Ident::new(&name, Span::call_site())
+}
+
+/// Generates the error enum of a `TryFrom` conversion, with one variant per fallible field, and returns it
+/// together with the error type of the impl. The enum declares the parameters of the conversion its variants use.
+pub fn error_enum(source: &DeriveInput, target: &DeriveInput, ident: &syn::Ident, mappings: &[FieldMapping], options: Options) -> (TokenStream, TokenStream) {
+    let fallible = targets(mappings)
+        .filter_map(|(name, mapping, _)| fallible_type(mapping, options).map(|ty| (name, ty)))
+        .collect::<Vec<_>>();
+    let mut generics = impl_generics(source, target, options);
+    let unused = generics.params.iter()
+        .map(param_ident)
+        .filter(|param| !fallible.iter().any(|(_, ty)| generic::mentions(ty.to_token_stream(), param)))
+        .cloned()
+        .collect::<Vec<_>>();
+    generic::prune(&mut generics, &unused);
+    let generic = fallible.iter()
+        .filter(|(_, ty)| generics.params.iter().any(|param| generic::mentions(ty.to_token_stream(), param_ident(param))))
+        .map(|(_, ty)| ty)
+        .collect::<Vec<_>>();
+    let bounded = |bounds: TokenStream| {
+        let mut generics = generics.clone();
+        let where_clause = generics.make_where_clause();
+        where_clause.predicates.extend(generic.iter().map(|ty| -> syn::WherePredicate { syn::parse_quote! { #ty: #bounds } }));
+        if where_clause.predicates.is_empty() {
+            generics.where_clause = None;
+        }
+        generics
+    };
+
+    let variants = fallible.iter().map(|(name, ty)| {
+        let variant = error_variant(name);
+        quote! { #variant(#ty) }
+    });
+    let debug_arms = fallible.iter().map(|(name, _)| {
+        let variant = error_variant(name);
+        let label = variant.to_string();
+        quote! { #ident::#variant(ref error) => f.debug_tuple(#label).field(error).finish() }
+    });
+    let display_arms = fallible.iter().map(|(name, _)| {
+        let variant = error_variant(name);
+        let message = format!("failed to convert field `{}`: {{}}", name);
+        quote! { #ident::#variant(ref error) => ::core::write!(f, #message, error) }
+    });
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let debug = bounded(quote! { ::core::fmt::Debug });
+    let (_, _, debug_where) = debug.split_for_impl();
+    let display = bounded(quote! { ::core::fmt::Display });
+    let (_, _, display_where) = display.split_for_impl();
+    let error = bounded(quote! { ::core::fmt::Debug + ::core::fmt::Display });
+    let (_, _, error_where) = error.split_for_impl();
+    let vis = &target.vis;
+
+    let definition = quote! {
+        #vis enum #ident #impl_generics #where_clause {
+            #(#variants),*
+        }
+        impl #impl_generics ::core::fmt::Debug for #ident #ty_generics #debug_where {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                match *self {
+                    #(#debug_arms,)*
+                }
+            }
+        }
+        impl #impl_generics ::core::fmt::Display for #ident #ty_generics #display_where {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                match *self {
+                    #(#display_arms,)*
+                }
+            }
+        }
+        impl #impl_generics ::std::error::Error for #ident #ty_generics #error_where {}
+    };
+
+    (definition, quote! { #ident #ty_generics })
+}
+
+/// The error enum of a `TryFrom` conversion into `target`, e.g. `YConversionError`.
+pub fn error_ident(target: &syn::Ident) -> syn::Ident {
+    quote::format_ident!("{}ConversionError", target)
+}
+
+/// A local variable for the field `name`, which can not clash with identifiers of the user.
+fn local_binding(name: &str) -> syn::Ident {
+    if let Some(name) = name.strip_prefix("r#") {
+        syn::Ident::new_raw(name, Span::mixed_site())
+    } else if name.starts_with(|c: char| c.is_ascii_digit()) {
+        quote::format_ident!("value{}", name, span = Span::mixed_site())
+    } else {
+        syn::Ident::new(name, Span::mixed_site())
+    }
+}
+
+/// Inverts the mappings, so they describe how to get from the derived type back to the source.
+///
+/// Renames are undone, added fields are dropped and removed fields are computed by their default.
+pub fn reverse(mappings: &[FieldMapping]) -> syn::Result<Vec<FieldMapping>> {
+    let mut target_indices = 0..;
+    let mut reversed = mappings.iter()
+        .map(|mapping| {
+            let source = mapping.target.clone()
+                .map(|target| (target_indices.next().unwrap(), target));
+            let target = mapping.source.as_ref().map(|(_, source)| source.clone());
+            let value = match &mapping.value {
+                FieldValue::Moved => FieldValue::Moved,
+                FieldValue::Retyped { span, with } => match with.as_ref().and_then(|with| with.back()) {
+                    Some(back) => FieldValue::Retyped { span: *span, with: Some(Box::new(back)) },
+                    None => return Err(syn::Error::new(
+                        *span,
+                        format!(
+                            "cannot generate a reverse conversion, field `{}` is retyped, convert it back with `with <expr> back <expr>`",
+                            source_name(mapping),
+                        ),
+                    )),
+                },
+                FieldValue::Added { span, .. } => FieldValue::Removed { span: *span, default: None },
+                FieldValue::Removed { default: Some(default), span } => FieldValue::Added {
+                    span: *span,
+                    init: Some(default.clone()),
+                },
+                FieldValue::Removed { default: None, span } => return Err(syn::Error::new(
+                    *span,
+                    format!(
+                        "cannot generate a reverse conversion, removed field `{}` needs a default, e.g. `= Default::default()`",
+                        source_name(mapping),
+                    ),
+                )),
+            };
+
+            Ok((mapping.source.as_ref().map(|(index, _)| *index), FieldMapping { source, target, value }))
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    // Keep the order of the original fields, dropped fields have no position anymore.
+    reversed.sort_by_key(|(index, _)| index.unwrap_or(usize::MAX));
+
+    Ok(reversed.into_iter().map(|(_, mapping)| mapping).collect())
+}
+
+fn source_name(mapping: &FieldMapping) -> String {
+    let (index, field) = mapping.source.as_ref().unwrap();
+    field_name(*index, field)
+}
+
+fn field_name(index: usize, field: &syn::Field) -> String {
+    field.ident.as_ref().map_or_else(|| index.to_string(), |ident| ident.to_string())
+}
+
+/// Wraps `body` into `impl From<Source> for Target`, or `From<&'a Source>` if the source is borrowed.
+pub fn from_impl(source: &DeriveInput, target: &DeriveInput, options: Options, body: TokenStream) -> TokenStream {
+    let generics = impl_generics(source, target, options);
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let source_ty = source_type(source, options);
+    let target_ident = &target.ident;
+    let target_generics = generic::arguments(&target.generics, options.substitutions);
+    let src = source_binding();
+
+    quote! {
+        impl #impl_generics ::core::convert::From<#source_ty> for #target_ident #target_generics #where_clause {
+            fn from(#src: #source_ty) -> Self {
+                #body
+            }
+        }
+    }
+}
+
+/// Wraps `body` into `impl TryFrom<Source> for Target` failing with `error`.
+pub fn try_from_impl(source: &DeriveInput, target: &DeriveInput, error: TokenStream, options: Options, body: TokenStream) -> TokenStream {
+    let generics = impl_generics(source, target, options);
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+    let source_ty = source_type(source, options);
+    let target_ident = &target.ident;
+    let target_generics = generic::arguments(&target.generics, options.substitutions);
+    let src = source_binding();
+
+    quote! {
+        impl #impl_generics ::core::convert::TryFrom<#source_ty> for #target_ident #target_generics #where_clause {
+            type Error = #error;
+
+            fn try_from(#src: #source_ty) -> ::core::result::Result<Self, Self::Error> {
+                ::core::result::Result::Ok({
+                    #body
+                })
+            }
+        }
+    }
+}
+
+/// The type the conversion starts from, `Source<...>` or `&'a Source<...>`.
+pub fn source_type(source: &DeriveInput, options: Options) -> TokenStream {
+    let source_ident = &source.ident;
+    let source_generics = generic::arguments(&source.generics, options.substitutions);
+    match options.borrow {
+        Some(lifetime) => quote! { &#lifetime #source_ident #source_generics },
+        None => quote! { #source_ident #source_generics },
+    }
+}
+
+/// The generics of the impl block, including the lifetime of a borrowed source.
+fn impl_generics(source: &DeriveInput, target: &DeriveInput, options: Options) -> syn::Generics {
+    let (mut source_generics, mut target_generics) = (source.generics.clone(), target.generics.clone());
+    generic::substitute_generics(options.substitutions, &mut source_generics);
+    generic::substitute_generics(options.substitutions, &mut target_generics);
+    let mut generics = merge_generics(&source_generics, &target_generics);
+    if let Some(lifetime) = options.borrow {
+        let declared = generics.lifetimes().any(|param| param.lifetime == *lifetime);
+        if !declared {
+            generics.params.insert(0, GenericParam::Lifetime(syn::LifetimeParam::new(lifetime.clone())));
+        }
+    }
+
+    generics
+}
+
+/// Combines the generics of both sides of a conversion, so the impl declares every parameter once.
+fn merge_generics(source: &syn::Generics, target: &syn::Generics) -> syn::Generics {
+    let mut generics = source.clone();
+    for param in &target.params {
+        match generics.params.iter_mut().find(|existing| param_ident(existing) == param_ident(param)) {
+            Some(GenericParam::Type(existing)) => if let GenericParam::Type(param) = param {
+                merge_bounds(&mut existing.bounds, &param.bounds);
+            },
+            Some(GenericParam::Lifetime(existing)) => if let GenericParam::Lifetime(param) = param {
+                merge_bounds(&mut existing.bounds, &param.bounds);
+            },
+            Some(GenericParam::Const(_)) => {},
+            None => generics.params.push(param.clone()),
+        }
+    }
+    generics.params = generics.params.iter()
+        .filter(|param| matches!(param, GenericParam::Lifetime(_)))
+        .chain(generics.params.iter().filter(|param| !matches!(param, GenericParam::Lifetime(_))))
+        .cloned()
+        .collect();
+
+    if let Some(target_where) = &target.where_clause {
+        let where_clause = generics.make_where_clause();
+        for predicate in &target_where.predicates {
+            let exists = where_clause.predicates.iter()
+                .any(|existing| existing.to_token_stream().to_string() == predicate.to_token_stream().to_string());
+            if !exists {
+                where_clause.predicates.push(predicate.clone());
+            }
+        }
+    }
+
+    generics
+}
+
+/// Adds the bounds a parameter has on the target only, e.g. after `~T: Clone + Send`.
+fn merge_bounds<T: ToTokens + Clone, P: Default>(bounds: &mut syn::punctuated::Punctuated<T, P>, additional: &syn::punctuated::Punctuated<T, P>) {
+    for bound in additional {
+        if !bounds.iter().any(|existing| existing.to_token_stream().to_string() == bound.to_token_stream().to_string()) {
+            bounds.push(bound.clone());
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse::ParseStream;
+
+mod kw {
+    syn::custom_keyword!(with);
+    syn::custom_keyword!(back);
+}
+
+/// A user supplied conversion for a changed field, written as `with <expr>`.
+/// The expression is either a path to a function or a closure taking the old value.
+///
+/// Fallible conversions are written as `try with <expr>: <Error>` and return a
+/// `Result` with the given error type.
+///
+/// The conversion back for `reverse` follows as `back <expr>`, e.g. `with f64::from back |v| v as f32`.
+#[derive(Clone)]
+pub struct Converter {
+    expr: syn::Expr,
+    error: Option<syn::Type>,
+    back: Option<syn::Expr>,
+}
+
+impl Converter {
+    /// Parses a trailing `with <expr>` or `try with <expr>: <Error>` if present.
+    pub fn parse_optional(input: ParseStream) -> syn::Result<Option<Box<Self>>> {
+        if input.peek(syn::Token![try]) {
+            input.parse::<syn::Token![try]>()?;
+            input.parse::<kw::with>()?;
+            let expr = input.parse()?;
+            if !input.peek(syn::Token![:]) {
+                return Err(input.error("expected `: <Error>` after a fallible conversion"));
+            }
+            input.parse::<syn::Token![:]>()?;
+
+            let error = Some(input.parse()?);
+            return Ok(Some(Box::new(Converter {
+                expr,
+                error,
+                back: input.call(parse_back)?,
+            })));
+        }
+
+        if !input.peek(kw::with) {
+            return Ok(None);
+        }
+
+        input.parse::<kw::with>()?;
+        Ok(Some(Box::new(Converter {
+            expr: input.parse()?,
+            error: None,
+            back: input.call(parse_back)?,
+        })))
+    }
+
+    /// The conversion back from the new value, if one is given with `back`.
+    pub fn back(&self) -> Option<Converter> {
+        self.back.clone().map(|expr| Converter { expr, error: None, back: None })
+    }
+
+    /// Whether the converter returns a `Result`.
+    pub fn is_fallible(&self) -> bool {
+        self.error.is_some()
+    }
+
+    /// The error type of a fallible converter.
+    pub fn error(&self) -> Option<&syn::Type> {
+        self.error.as_ref()
+    }
+
+    /// Calls the converter with `value`.
+    pub fn call(&self, value: TokenStream) -> TokenStream {
+        let expr = &self.expr;
+        match expr {
+            syn::Expr::Path(_) => quote! { #expr(#value) },
+            _ => quote! { (#expr)(#value) },
+        }
+    }
+}
+
+/// Parses a trailing `back <expr>` if present.
+fn parse_back(input: ParseStream) -> syn::Result<Option<syn::Expr>> {
+    if !input.peek(kw::back) {
+        return Ok(None);
+    }
+
+    input.parse::<kw::back>()?;
+    Ok(Some(input.parse()?))
+}
@@ -0,0 +1,141 @@
+use syn::parse::{Parse, ParseStream};
+
+use crate::converter::Converter;
+
+/// A container level instruction, written before the attributes of the derived type
+/// and terminated by a comma, e.g. `#[mapstruct(from, struct Y { ... })]`.
+pub enum Directive {
+    /// Generate `From<Source> for Target`, indicated by `from`.
+    /// With `from(&'a)` the source is borrowed instead, i.e. `From<&'a Source> for Target`.
+    From(syn::Ident, Option<syn::Lifetime>),
+    /// Generate `From<Target> for Source`, indicated by `reverse`.
+    Reverse(syn::Ident),
+    /// Generate `TryFrom<Source> for Target` and its error enum, indicated by `try_from`.
+    /// Like `from`, `try_from(&'a)` borrows the source.
+    TryFrom(syn::Ident, Option<syn::Lifetime>),
+    /// Start from the attributes of the source, indicated by `inherit`.
+    /// The attributes of the derived type then add to them with `+#[...]` and remove with `-#[...]`.
+    Inherit(syn::Ident),
+    /// Reorder the fields or variants of the result, written as `order(id, name)`.
+    /// Tuple fields are listed by position.
+    Order(syn::Ident, Vec<syn::Member>),
+    /// Keep generic parameters no field uses with a `PhantomData` field, indicated by `phantom`.
+    /// Enums keep them with a hidden variant instead. Otherwise they are removed.
+    Phantom(syn::Ident),
+    /// Replace a type in every field taken over from the source, including nested uses,
+    /// written as `replace String => Arc<str>`. Retyped and added fields keep the type written for them.
+    /// Fields of exactly that type are converted by an optional `with`. It can be given more than once.
+    Replace(syn::Ident, Box<syn::Type>, Box<syn::Type>, Option<Box<Converter>>),
+    /// Declare a single field tuple struct, written as `newtype UserId(i64)`.
+    /// Retyping a field of that type to the wrapped one unwraps it with `.0`. It can be given more than once.
+    Newtype(syn::Ident, syn::Path, Box<syn::Type>),
+}
+
+impl Parse for Directive {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident = input.parse::<syn::Ident>()?;
+
+        match ident.to_string().as_str() {
+            "from" => Ok(Directive::From(ident, input.call(parse_borrow)?)),
+            "reverse" => Ok(Directive::Reverse(ident)),
+            "try_from" => Ok(Directive::TryFrom(ident, input.call(parse_borrow)?)),
+            "inherit" => Ok(Directive::Inherit(ident)),
+            "phantom" => Ok(Directive::Phantom(ident)),
+            "replace" => {
+                let from = input.parse()?;
+                input.parse::<syn::Token![=>]>()?;
+                let to = input.parse()?;
+                Ok(Directive::Replace(ident, from, to, input.call(Converter::parse_optional)?))
+            },
+            "newtype" => {
+                let path = input.parse()?;
+                let content;
+                syn::parenthesized!(content in input);
+                Ok(Directive::Newtype(ident, path, content.parse()?))
+            },
+            "order" => {
+                let content;
+                syn::parenthesized!(content in input);
+                let members = content.parse_terminated(syn::Member::parse, syn::Token![,])?;
+                Ok(Directive::Order(ident, members.into_iter().collect()))
+            },
+            _ => Err(syn::Error::new_spanned(ident, "unknown directive, expected one of `from`, `reverse`, `try_from`, `inherit`, `order`, `phantom`, `replace`, `newtype`")),
+        }
+    }
+}
+
+impl Directive {
+    pub fn ident(&self) -> &syn::Ident {
+        match self {
+            Directive::From(ident, _) => ident,
+            Directive::Reverse(ident) => ident,
+            Directive::TryFrom(ident, _) => ident,
+            Directive::Inherit(ident) => ident,
+            Directive::Order(ident, _) => ident,
+            Directive::Phantom(ident) => ident,
+            Directive::Replace(ident, ..) => ident,
+            Directive::Newtype(ident, ..) => ident,
+        }
+    }
+
+    /// The order of the fields or variants if given.
+    pub fn order(directives: &[Directive]) -> Option<&[syn::Member]> {
+        directives.iter().find_map(|directive| match directive {
+            Directive::Order(_, members) => Some(members.as_slice()),
+            _ => None,
+        })
+    }
+
+    /// The lifetime the source is borrowed for by `from(&'a)` or `try_from(&'a)`.
+    pub fn borrow(directives: &[Directive]) -> Option<&syn::Lifetime> {
+        directives.iter().find_map(|directive| match directive {
+            Directive::From(_, borrow) | Directive::TryFrom(_, borrow) => borrow.as_ref(),
+            _ => None,
+        })
+    }
+
+    /// The declared newtypes with the type they wrap.
+    pub fn newtypes(directives: &[Directive]) -> Vec<(&syn::Path, &syn::Type)> {
+        directives.iter()
+            .filter_map(|directive| match directive {
+                Directive::Newtype(_, path, inner) => Some((path, &**inner)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Parses all leading directives. Keywords like `pub`, `struct` or `enum` end the list.
+    pub fn parse_all(input: ParseStream) -> syn::Result<Vec<Self>> {
+        let mut directives = Vec::new();
+        while input.peek(syn::Ident) {
+            let directive = input.parse::<Directive>()?;
+            let repeatable = matches!(directive, Directive::Replace(..) | Directive::Newtype(..));
+            if !repeatable && directives.iter().any(|existing: &Directive| existing.ident() == directive.ident()) {
+                return Err(syn::Error::new_spanned(directive.ident(), "duplicate directive"));
+            }
+            directives.push(directive);
+            input.parse::<syn::Token![,]>()?;
+        }
+
+        let from = directives.iter().any(|directive| matches!(directive, Directive::From(..)));
+        if let Some(Directive::TryFrom(ident, _)) = directives.iter().find(|directive| matches!(directive, Directive::TryFrom(..))) {
+            if from {
+                return Err(syn::Error::new_spanned(ident, "`try_from` conflicts with `from`, choose one of them"));
+            }
+        }
+
+        Ok(directives)
+    }
+}
+
+/// Parses the optional `(&'a)` of a borrowing conversion.
+fn parse_borrow(input: ParseStream) -> syn::Result<Option<syn::Lifetime>> {
+    if !input.peek(syn::token::Paren) {
+        return Ok(None);
+    }
+
+    let content;
+    syn::parenthesized!(content in input);
+    content.parse::<syn::Token![&]>()?;
+    Ok(Some(content.parse()?))
+}
@@ -1,12 +1,20 @@
-use syn::{DeriveInput, GenericParam};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::DeriveInput;
+use syn::punctuated::Punctuated;
 use syn::parse::Parse;
 
+use crate::attribute_change::AttributeChange;
+use crate::conversion::{self, Options, VariantMapping, VariantValue};
+use crate::directive::Directive;
 use crate::enum_change::EnumChange;
-use crate::generic::GenericChange;
-use crate::transformer::Transformer;
+use crate::generic::{self, GenericChange};
+use crate::order;
+use crate::replace::Replacements;
 
 pub struct MapEnum {
-    attrs: Vec<syn::Attribute>,
+    directives: Vec<Directive>,
+    attrs: Vec<AttributeChange>,
     vis: syn::Visibility,
     ident: syn::Ident,
     generics: Vec<GenericChange>,
@@ -15,7 +23,8 @@ pub struct MapEnum {
 
 impl Parse for MapEnum {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        let attrs = input.call(syn::Attribute::parse_outer)?;
+        let directives = input.call(Directive::parse_all)?;
+        let attrs = input.call(AttributeChange::parse_container)?;
         let vis = input.parse::<syn::Visibility>()?;
         input.parse::<syn::Token![enum]>()?;
         let ident = input.parse()?;
@@ -39,6 +48,7 @@ impl Parse for MapEnum {
         let changes = input.parse()?;
 
         Ok(MapEnum {
+            directives,
             attrs,
             vis,
             ident,
@@ -49,41 +59,107 @@ impl Parse for MapEnum {
 }
 
 impl MapEnum {
-    pub fn transform(self, input: DeriveInput) -> syn::Result<DeriveInput> {
-        let mut input = input;
-        input.attrs = self.attrs;
+    pub fn transform(self, source: DeriveInput) -> syn::Result<TokenStream> {
+        let mut input = source.clone();
+        input.attrs = if self.directives.iter().any(|directive| matches!(directive, Directive::Inherit(_))) {
+            AttributeChange::inherited(&source.attrs)
+        } else {
+            Vec::new()
+        };
+        AttributeChange::apply(&self.attrs, &mut input.attrs)?;
         input.vis = self.vis;
         input.ident = self.ident;
 
-        let generic_removes = self.generics.iter()
-            .filter(|change| matches!(change, GenericChange::Remove(_)))
-            .map(|change| crate::unwrap_one_variant!(change, GenericChange::Remove(lifetime), lifetime))
-            .collect::<Vec<_>>();
-        input.generics.params = input.generics.params.into_iter()
-            .filter(|param| match param {
-                GenericParam::Lifetime(param) => {
-                    !generic_removes.contains(&&param.lifetime.ident)
-                },
-                GenericParam::Type(param) => {
-                    !generic_removes.contains(&&param.ident)
-                },
-                GenericParam::Const(param) => {
-                    !generic_removes.contains(&&param.ident)
-                },
-            })
-            .collect();
-        input.generics.params.extend(self.generics.into_iter()
-            .filter(|change| matches!(change, GenericChange::Add(_)))
-            .map(|change| crate::unwrap_one_variant!(change, GenericChange::Add(param), param))
-        );
-
-        match &mut input.data {
+        let removed = GenericChange::removed(&self.generics);
+        let substitutions = GenericChange::apply(self.generics, &mut input.generics)?;
+
+        let mut mappings = match &mut input.data {
             syn::Data::Enum(data) => {
-                self.changes.transform(data)?;
+                let mut mappings = self.changes.apply(data)?;
+                if let Some(order) = Directive::order(&self.directives) {
+                    data.variants = order::variants(order, &mut mappings)?.into_iter().collect();
+                }
+
+                mappings
             },
             _ => return Err(syn::Error::new_spanned(input, "expected enum")),
+        };
+
+        let variants = crate::unwrap_one_variant!(&mut input.data, syn::Data::Enum(data), &mut data.variants);
+        let mut replacements = Replacements::new(&self.directives);
+        let targets = mappings.iter_mut().filter(|mapping| mapping.target.is_some());
+        for (variant, mapping) in variants.iter_mut().zip(targets) {
+            if let VariantValue::Mapped(fields) = &mut mapping.value {
+                let targets = fields.iter_mut().filter(|mapping| mapping.target.is_some());
+                for (field, mapping) in variant.fields.iter_mut().zip(targets) {
+                    replacements.field(field, mapping);
+                }
+            }
+            mapping.target = Some(variant.clone());
         }
+        replacements.finish()?;
 
-        Ok(input)
+        generic::substitute(&substitutions, &mut input);
+
+        let variants = crate::unwrap_one_variant!(&mut input.data, syn::Data::Enum(data), &mut data.variants);
+        let borrow = Directive::borrow(&self.directives);
+        let fields = variants.iter_mut().flat_map(|variant| variant.fields.iter_mut());
+        generic::infer_lifetimes(&mut input.generics, &source.generics, &removed, borrow, fields);
+        let fields = variants.iter().flat_map(|variant| variant.fields.iter());
+        generic::check_removed(&source.generics, &removed, fields.clone())?;
+        let unused = generic::unused(&input.generics, fields);
+        let phantom = self.directives.iter().any(|directive| matches!(directive, Directive::Phantom(_)));
+        if phantom && !unused.is_empty() {
+            add_phantom(variants, &mut mappings, &unused);
+        } else {
+            generic::prune(&mut input.generics, &unused.iter().map(generic::param_ident).cloned().collect::<Vec<_>>());
+        }
+
+        let newtypes = Directive::newtypes(&self.directives);
+        let mut impls = Vec::new();
+        for directive in &self.directives {
+            match directive {
+                Directive::From(_, borrow) => {
+                    let options = Options { borrow: borrow.as_ref(), substitutions: &substitutions, newtypes: &newtypes, ..Options::default() };
+                    let body = conversion::match_variants(&source.ident, &mappings, options, false)?;
+                    impls.push(conversion::from_impl(&source, &input, options, body));
+                },
+                Directive::Reverse(_) => {
+                    let options = Options { substitutions: &substitutions, newtypes: &newtypes, ..Options::default() };
+                    let mappings = conversion::reverse_variants(&mappings)?;
+                    let body = conversion::match_variants(&input.ident, &mappings, options, false)?;
+                    impls.push(conversion::from_impl(&input, &source, options, body));
+                },
+                Directive::TryFrom(_, borrow) => {
+                    // The error returns the source, so there is no room for errors of fields.
+                    let options = Options { borrow: borrow.as_ref(), substitutions: &substitutions, newtypes: &newtypes, ..Options::default() };
+                    let body = conversion::match_variants(&source.ident, &mappings, options, true)?;
+                    let error = conversion::source_type(&source, options);
+                    impls.push(conversion::try_from_impl(&source, &input, error, options, body));
+                },
+                Directive::Inherit(_) | Directive::Order(..) | Directive::Phantom(_) | Directive::Replace(..)
+                | Directive::Newtype(..) => {},
+            }
+        }
+
+        Ok(quote! {
+            #input
+            #(#impls)*
+        })
     }
 }
+
+/// Keeps `params` in use with a hidden `_Phantom(Infallible, PhantomData<(T,)>)` variant, which can not be constructed.
+fn add_phantom(variants: &mut Punctuated<syn::Variant, syn::Token![,]>, mappings: &mut Vec<VariantMapping>, params: &[syn::GenericParam]) {
+    let ty = generic::phantom(params);
+    let variant: syn::Variant = syn::parse_quote! {
+        #[doc(hidden)]
+        _Phantom(::core::convert::Infallible, #ty)
+    };
+    variants.push(variant.clone());
+    mappings.push(VariantMapping {
+        source: None,
+        target: Some(variant),
+        value: VariantValue::Phantom,
+    });
+}
@@ -1,5 +1,6 @@
 use syn::parse::{Parse, ParseStream};
 
+use crate::conversion::{self, VariantMapping, VariantValue};
 use crate::transformer::Transformer;
 use crate::variant::VariantChange;
 
@@ -27,6 +28,20 @@ impl Transformer for EnumChange {
     }
 
     fn transform(&self, item: &mut Self::Item) -> syn::Result<bool> {
+        self.apply(item)?;
+
+        Ok(true)
+    }
+
+    fn remove(&self, _: &Self::Item) -> syn::Result<bool> {
+        Ok(false)
+    }
+}
+
+impl EnumChange {
+    /// Applies the changes to `item` and returns how every variant of the source
+    /// and the result relate to each other.
+    pub fn apply(&self, item: &mut syn::DataEnum) -> syn::Result<Vec<VariantMapping>> {
         #[derive(Copy, Clone, PartialEq)]
         enum VariantChange {
             Original,
@@ -39,15 +54,23 @@ impl Transformer for EnumChange {
             .cloned()
             .map(|field| (field, VariantChange::Original))
             .collect::<Vec<_>>();
+        let mut values = item.variants.iter()
+            .map(|variant| (Some(variant.clone()), VariantValue::Mapped(conversion::unchanged(&variant.fields))))
+            .collect::<Vec<_>>();
 
         for variant_change in &self.changes {
             let mut applied = false;
             for variant in variant_change.create()? {
-                new_variants.push((variant, VariantChange::Added));
+                let index = match variant_change.position() {
+                    Some(position) => position.index(new_variants.iter().map(|(variant, _)| Some(&variant.ident)))?,
+                    None => new_variants.len(),
+                };
+                new_variants.insert(index, (variant, VariantChange::Added));
+                values.insert(index, (None, VariantValue::Added { span: variant_change.span() }));
                 applied = true;
             }
 
-            for (variant, change) in &mut new_variants {
+            for ((variant, change), (_, value)) in new_variants.iter_mut().zip(values.iter_mut()) {
                 let do_remove = variant_change.remove(variant)?;
                 if do_remove && change != &VariantChange::Original {
                     // Already changed
@@ -56,7 +79,8 @@ impl Transformer for EnumChange {
                         "Cannot change field twice"
                     ));
                 }
-                let transform = variant_change.transform(variant)?;
+                let mapped = variant_change.apply(variant)?;
+                let transform = mapped.is_some();
                 if transform && change != &VariantChange::Original {
                     // Already changed
                     return Err(syn::Error::new_spanned(
@@ -73,10 +97,12 @@ impl Transformer for EnumChange {
                         ));
                     } else {
                         *change = VariantChange::Changed;
+                        *value = mapped.unwrap();
                         applied = true;
                     }
                 } else if do_remove {
                     *change = VariantChange::Removed;
+                    *value = VariantValue::Removed { span: variant_change.span() };
                     applied = true;
                 }
             }
@@ -89,6 +115,18 @@ impl Transformer for EnumChange {
             }
         }
 
+        let mappings = new_variants.iter()
+            .zip(values)
+            .map(|((variant, change), (source, value))| VariantMapping {
+                source,
+                target: match change {
+                    VariantChange::Removed => None,
+                    _ => Some(variant.clone()),
+                },
+                value,
+            })
+            .collect();
+
         item.variants = new_variants.into_iter()
             .filter_map(|field| match field {
                 (field, VariantChange::Original) => Some(field),
@@ -98,10 +136,6 @@ impl Transformer for EnumChange {
             })
             .collect();
 
-        Ok(true)
-    }
-
-    fn remove(&self, _: &Self::Item) -> syn::Result<bool> {
-        Ok(false)
+        Ok(mappings)
     }
 }
@@ -1,23 +1,633 @@
+use proc_macro2::{TokenStream, TokenTree};
+use quote::{quote, ToTokens};
 use syn::parse::Parse;
+use syn::punctuated::Punctuated;
+use syn::visit_mut::VisitMut;
+
+use crate::order::Position;
 
 pub enum GenericChange {
-    Add(syn::GenericParam),
+    /// Add a parameter, written as `+T: Clone`. It is placed in canonical order, lifetimes first,
+    /// then types and then consts, unless a position like `+U before T` or `+before(T) U` is given.
+    Add(Option<Position>, syn::GenericParam),
     Remove(syn::Ident),
+    /// Add a where predicate, written as `+where T: Send`.
+    AddWhere(syn::WherePredicate),
+    /// Remove bounds from a where predicate, written as `-where T: Clone`.
+    RemoveWhere(syn::WherePredicate),
+    /// Change a parameter in place, written as `~T: Clone + Send`, `~T = String` or `~const N: usize = 8`.
+    /// Bounds and defaults which are not written are kept.
+    Change(syn::GenericParam),
+    /// Replace a parameter with a concrete type, value or lifetime everywhere,
+    /// written as `~T => String`, `~N => 16` or `~'a => 'static`.
+    Substitute(syn::Ident, syn::GenericArgument),
+    /// Rename a parameter and every use of it, written as `~T -> Item`, `~'a -> 'src` or `~N -> LEN`.
+    /// Lifetimes are stored without their `'`.
+    Rename(syn::Ident, syn::Ident),
+}
+
+/// A parameter of the source which the derived type replaces with a concrete argument,
+/// or with another parameter if it is renamed.
+#[derive(Clone)]
+pub struct Substitution {
+    pub ident: syn::Ident,
+    pub value: syn::GenericArgument,
+    pub rename: bool,
 }
 
 impl Parse for GenericChange {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        if input.peek(syn::Token![+]) && input.peek2(syn::Token![where]) {
+            input.parse::<syn::Token![+]>()?;
+            input.parse::<syn::Token![where]>()?;
+            return Ok(GenericChange::AddWhere(input.parse()?));
+        }
+        if input.peek(syn::Token![-]) && input.peek2(syn::Token![where]) {
+            input.parse::<syn::Token![-]>()?;
+            input.parse::<syn::Token![where]>()?;
+            return Ok(GenericChange::RemoveWhere(input.parse()?));
+        }
         if input.peek(syn::Token![+]) {
             input.parse::<syn::Token![+]>()?;
+            let leading = input.call(Position::parse_optional)?;
             let generic_param = input.parse()?;
-            return Ok(GenericChange::Add(generic_param));
+            let position = match (leading, input.call(Position::parse_trailing)?) {
+                (Some(_), Some(_)) => return Err(input.error("the position is already given in front of the parameter")),
+                (leading, trailing) => leading.or(trailing),
+            };
+            return Ok(GenericChange::Add(position, generic_param));
         }
         if input.peek(syn::Token![-]) {
             input.parse::<syn::Token![-]>()?;
+            if input.peek(syn::Lifetime) {
+                return Ok(GenericChange::Remove(input.parse::<syn::Lifetime>()?.ident));
+            }
             let lifetime = input.parse()?;
             return Ok(GenericChange::Remove(lifetime));
         }
+        if input.peek(syn::Token![~]) {
+            input.parse::<syn::Token![~]>()?;
+            if input.peek(syn::Lifetime) && input.peek2(syn::Token![->]) {
+                let lifetime = input.parse::<syn::Lifetime>()?;
+                input.parse::<syn::Token![->]>()?;
+                return Ok(GenericChange::Rename(lifetime.ident, input.parse::<syn::Lifetime>()?.ident));
+            }
+            if input.peek(syn::Ident) && input.peek2(syn::Token![->]) {
+                let ident = input.parse()?;
+                input.parse::<syn::Token![->]>()?;
+                return Ok(GenericChange::Rename(ident, input.parse()?));
+            }
+            if input.peek(syn::Lifetime) && input.peek2(syn::Token![=>]) {
+                let lifetime = input.parse::<syn::Lifetime>()?;
+                input.parse::<syn::Token![=>]>()?;
+                return Ok(GenericChange::Substitute(lifetime.ident, syn::GenericArgument::Lifetime(input.parse()?)));
+            }
+            if input.peek(syn::Ident) && input.peek2(syn::Token![=>]) {
+                let ident = input.parse()?;
+                input.parse::<syn::Token![=>]>()?;
+                return Ok(GenericChange::Substitute(ident, input.parse()?));
+            }
+            return Ok(GenericChange::Change(input.parse()?));
+        }
+
+        Err(input.error("expected one of +, -, ~"))
+    }
+}
+
+impl GenericChange {
+    /// Applies the changes to the generics of the derived type.
+    ///
+    /// Where predicates mentioning a removed parameter are dropped along with it.
+    /// Substituted parameters are removed as well and renamed parameters are renamed right away,
+    /// their uses are replaced with [`substitute`] once the fields are final.
+    pub fn apply(changes: Vec<GenericChange>, generics: &mut syn::Generics) -> syn::Result<Vec<Substitution>> {
+        let mut substitutions = Vec::new();
+        for change in &changes {
+            match change {
+                GenericChange::Substitute(ident, value) => {
+                    let param = find_param(generics, ident)?;
+                    substitutions.push(Substitution {
+                        ident: ident.clone(),
+                        value: argument(param, value.clone())?,
+                        rename: false,
+                    });
+                },
+                GenericChange::Rename(ident, to) => {
+                    if generics.params.iter().any(|param| param_ident(param) == to) {
+                        return Err(syn::Error::new_spanned(to, "A generic parameter with this name exists already"));
+                    }
+                    let param = generics.params.iter_mut()
+                        .find(|param| param_ident(param) == ident)
+                        .ok_or_else(|| syn::Error::new_spanned(ident, "No generic parameter with this name"))?;
+                    substitutions.push(Substitution {
+                        ident: ident.clone(),
+                        value: reference(kind(param), to.clone()),
+                        rename: true,
+                    });
+                    match param {
+                        syn::GenericParam::Lifetime(param) => param.lifetime.ident = to.clone(),
+                        syn::GenericParam::Type(param) => param.ident = to.clone(),
+                        syn::GenericParam::Const(param) => param.ident = to.clone(),
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        prune(generics, &GenericChange::removed(&changes));
+        generics.params = std::mem::take(&mut generics.params).into_iter()
+            .filter(|param| !substitutions.iter().any(|substitution| !substitution.rename && &substitution.ident == param_ident(param)))
+            .collect();
+        sort(generics);
+
+        for change in changes {
+            match change {
+                GenericChange::Add(position, param) => {
+                    if substitutions.iter().any(|substitution| &substitution.ident == param_ident(&param)) {
+                        return Err(syn::Error::new_spanned(param, "Cannot add a parameter which is substituted or renamed"));
+                    }
+                    match position {
+                        Some(position) => {
+                            let index = position.index(generics.params.iter().map(|param| Some(param_ident(param))))?;
+                            let misplaced = generics.params.iter().take(index).any(|before| kind(before) > kind(&param))
+                                || generics.params.iter().skip(index).any(|after| kind(after) < kind(&param));
+                            if misplaced {
+                                return Err(syn::Error::new_spanned(param, "Lifetimes have to come before types and types before consts"));
+                            }
+                            generics.params.insert(index, param);
+                        },
+                        None => {
+                            let index = generics.params.iter().filter(|existing| kind(existing) <= kind(&param)).count();
+                            generics.params.insert(index, param);
+                        },
+                    }
+                },
+                GenericChange::Remove(_) | GenericChange::Substitute(..) | GenericChange::Rename(..) => {},
+                GenericChange::AddWhere(predicate) => generics.make_where_clause().predicates.push(predicate),
+                GenericChange::RemoveWhere(predicate) => remove_predicate(generics, predicate)?,
+                GenericChange::Change(change) => {
+                    let param = generics.params.iter_mut()
+                        .find(|param| param_ident(param) == param_ident(&change))
+                        .ok_or_else(|| syn::Error::new_spanned(param_ident(&change), "No generic parameter with this name"))?;
+                    change_param(param, change)?;
+                },
+            }
+        }
+
+        if generics.where_clause.as_ref().is_some_and(|where_clause| where_clause.predicates.is_empty()) {
+            generics.where_clause = None;
+        }
+
+        Ok(substitutions)
+    }
+
+    /// The parameters removed with `-`.
+    pub fn removed(changes: &[GenericChange]) -> Vec<syn::Ident> {
+        changes.iter()
+            .filter_map(|change| match change {
+                GenericChange::Remove(ident) => Some(ident.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Removes the parameters `removed` along with every bound and where predicate mentioning them.
+pub fn prune(generics: &mut syn::Generics, removed: &[syn::Ident]) {
+    let mentioned = |bound: &dyn ToTokens| removed.iter().any(|ident| mentions(bound.to_token_stream(), ident));
+    generics.params = std::mem::take(&mut generics.params).into_iter()
+        .filter(|param| !removed.contains(param_ident(param)))
+        .map(|mut param| {
+            match &mut param {
+                syn::GenericParam::Lifetime(param) => {
+                    param.bounds = std::mem::take(&mut param.bounds).into_iter().filter(|bound| !mentioned(bound)).collect();
+                },
+                syn::GenericParam::Type(param) => {
+                    param.bounds = std::mem::take(&mut param.bounds).into_iter().filter(|bound| !mentioned(bound)).collect();
+                },
+                syn::GenericParam::Const(_) => {},
+            }
+            param
+        })
+        .collect();
+    if let Some(where_clause) = &mut generics.where_clause {
+        where_clause.predicates = std::mem::take(&mut where_clause.predicates).into_iter()
+            .filter(|predicate| !mentioned(predicate))
+            .collect();
+    }
+}
+
+/// The lifetime and type parameters no field mentions, which rustc rejects as unused.
+/// Const parameters may be unused.
+pub fn unused<'a>(generics: &syn::Generics, fields: impl Iterator<Item = &'a syn::Field> + Clone) -> Vec<syn::GenericParam> {
+    generics.params.iter()
+        .filter(|param| !matches!(param, syn::GenericParam::Const(_)))
+        .filter(|param| !fields.clone().any(|field| mentions(field.ty.to_token_stream(), param_ident(param))))
+        .cloned()
+        .collect()
+}
+
+/// Fails on the first field still mentioning one of the `removed` parameters of `source`.
+pub fn check_removed<'a>(source: &syn::Generics, removed: &[syn::Ident], fields: impl Iterator<Item = &'a syn::Field>) -> syn::Result<()> {
+    for field in fields {
+        let param = source.params.iter()
+            .filter(|param| removed.contains(param_ident(param)))
+            .find(|param| mentions(field.ty.to_token_stream(), param_ident(param)));
+        if let Some(param) = param {
+            let name = match param {
+                syn::GenericParam::Lifetime(param) => param.lifetime.to_string(),
+                param => param_ident(param).to_string(),
+            };
+            return Err(syn::Error::new_spanned(
+                field,
+                format!("Generic parameter `{}` is removed but still used by this field", name),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Declares the lifetimes fields use without declaring them, e.g. `+name: &'a str`, after the declared lifetimes.
+/// Elided references like `&str` get one fresh lifetime, the borrow of the conversion if it is not declared yet.
+/// Parameters in `removed` are not declared again.
+pub fn infer_lifetimes<'a>(
+    generics: &mut syn::Generics,
+    source: &syn::Generics,
+    removed: &[syn::Ident],
+    borrow: Option<&syn::Lifetime>,
+    fields: impl Iterator<Item = &'a mut syn::Field>,
+) {
+    let declared = |lifetime: &syn::Lifetime, generics: &syn::Generics| generics.lifetimes().any(|param| &param.lifetime == lifetime);
+    let borrow = borrow.filter(|borrow| !declared(borrow, generics));
+    let taken = |lifetime: &syn::Lifetime| {
+        declared(lifetime, generics) || declared(lifetime, source) || removed.contains(&lifetime.ident)
+    };
+    let mut lifetimes = Lifetimes {
+        fresh: None,
+        make_fresh: || borrow.cloned().unwrap_or_else(|| fresh_lifetime(taken)),
+        used: Vec::new(),
+    };
+    for field in fields {
+        lifetimes.visit_type_mut(&mut field.ty);
+    }
+
+    let mut index = generics.lifetimes().count();
+    for lifetime in lifetimes.used {
+        let known = declared(&lifetime, generics) || removed.contains(&lifetime.ident) || lifetime.ident == "static";
+        if !known {
+            generics.params.insert(index, syn::GenericParam::Lifetime(syn::LifetimeParam::new(lifetime)));
+            index += 1;
+        }
+    }
+}
+
+/// The first lifetime from `'a` to `'z` which is not `taken`, otherwise `'__mapstruct0`, `'__mapstruct1` and so on.
+fn fresh_lifetime(taken: impl Fn(&syn::Lifetime) -> bool) -> syn::Lifetime {
+    let lifetime = |name: String| syn::Lifetime::new(&format!("'{}", name), proc_macro2::Span::call_site());
+    for name in 'a'..='z' {
+        let lifetime = lifetime(name.to_string());
+        if !taken(&lifetime) {
+            return lifetime;
+        }
+    }
+
+    let mut index = 0;
+    loop {
+        let lifetime = lifetime(format!("__mapstruct{}", index));
+        if !taken(&lifetime) {
+            return lifetime;
+        }
+        index += 1;
+    }
+}
+
+/// Collects the lifetimes of field types in order of appearance and names elided ones.
+/// The name of elided lifetimes is only made once one is found.
+/// Higher ranked types like `fn(&str)` or `for<'b> Fn(&'b str)` are left alone.
+struct Lifetimes<F> {
+    fresh: Option<syn::Lifetime>,
+    make_fresh: F,
+    used: Vec<syn::Lifetime>,
+}
+
+impl<F: Fn() -> syn::Lifetime> VisitMut for Lifetimes<F> {
+    fn visit_type_reference_mut(&mut self, reference: &mut syn::TypeReference) {
+        if reference.lifetime.is_none() {
+            reference.lifetime = Some(syn::Lifetime::new("'_", reference.and_token.span));
+        }
+        syn::visit_mut::visit_type_reference_mut(self, reference);
+    }
+
+    fn visit_type_bare_fn_mut(&mut self, _: &mut syn::TypeBareFn) {}
+
+    fn visit_parenthesized_generic_arguments_mut(&mut self, _: &mut syn::ParenthesizedGenericArguments) {}
+
+    fn visit_trait_bound_mut(&mut self, bound: &mut syn::TraitBound) {
+        if bound.lifetimes.is_none() {
+            syn::visit_mut::visit_trait_bound_mut(self, bound);
+        }
+    }
+
+    fn visit_lifetime_mut(&mut self, lifetime: &mut syn::Lifetime) {
+        if lifetime.ident == "_" {
+            *lifetime = self.fresh.get_or_insert_with(&self.make_fresh).clone();
+        }
+        if !self.used.contains(lifetime) {
+            self.used.push(lifetime.clone());
+        }
+    }
+}
+
+/// The type of a field keeping `params` in use, `PhantomData<(&'a (), T)>`.
+pub fn phantom(params: &[syn::GenericParam]) -> syn::Type {
+    let markers = params.iter().map(|param| match param {
+        syn::GenericParam::Lifetime(param) => {
+            let lifetime = &param.lifetime;
+            quote! { &#lifetime () }
+        },
+        param => param_ident(param).to_token_stream(),
+    });
+    syn::parse_quote! { ::core::marker::PhantomData<(#(#markers,)*)> }
+}
+
+fn find_param<'a>(generics: &'a syn::Generics, ident: &syn::Ident) -> syn::Result<&'a syn::GenericParam> {
+    generics.params.iter()
+        .find(|param| param_ident(param) == ident)
+        .ok_or_else(|| syn::Error::new_spanned(ident, "No generic parameter with this name"))
+}
 
-        Err(input.error("expected one of +, -"))
+/// A use of the parameter `ident` of the given [`kind`].
+fn reference(kind: u8, ident: syn::Ident) -> syn::GenericArgument {
+    match kind {
+        0 => syn::GenericArgument::Lifetime(syn::Lifetime { apostrophe: ident.span(), ident }),
+        1 => syn::GenericArgument::Type(syn::Type::Path(syn::TypePath { qself: None, path: ident.into() })),
+        _ => syn::GenericArgument::Const(syn::Expr::Path(syn::ExprPath { attrs: Vec::new(), qself: None, path: ident.into() })),
     }
 }
+
+impl Substitution {
+    /// Renames a renamed parameter back, from its new name to the one of the source.
+    fn reverse(&self) -> Option<Substitution> {
+        let (kind, renamed) = match &self.value {
+            syn::GenericArgument::Lifetime(lifetime) => (0, &lifetime.ident),
+            syn::GenericArgument::Type(syn::Type::Path(path)) => (1, path.path.get_ident()?),
+            syn::GenericArgument::Const(syn::Expr::Path(path)) => (2, path.path.get_ident()?),
+            _ => return None,
+        };
+
+        Some(Substitution {
+            ident: renamed.clone(),
+            value: reference(kind, self.ident.clone()),
+            rename: false,
+        })
+    }
+}
+
+/// The substitutions as seen by generated conversions, which name both types with the parameters of the source.
+fn in_conversions(substitutions: &[Substitution]) -> Vec<Substitution> {
+    substitutions.iter()
+        .filter_map(|substitution| match substitution.rename {
+            true => substitution.reverse(),
+            false => Some(substitution.clone()),
+        })
+        .collect()
+}
+
+/// Checks that `value` can stand in for `param`. A const given by name is parsed as a type and turned into an expression.
+fn argument(param: &syn::GenericParam, value: syn::GenericArgument) -> syn::Result<syn::GenericArgument> {
+    match (param, value) {
+        (syn::GenericParam::Lifetime(_), value @ syn::GenericArgument::Lifetime(_))
+        | (syn::GenericParam::Type(_), value @ syn::GenericArgument::Type(_))
+        | (syn::GenericParam::Const(_), value @ syn::GenericArgument::Const(_)) => Ok(value),
+        (syn::GenericParam::Const(_), syn::GenericArgument::Type(syn::Type::Path(path))) if path.qself.is_none() => {
+            Ok(syn::GenericArgument::Const(syn::Expr::Path(syn::ExprPath { attrs: Vec::new(), qself: None, path: path.path })))
+        },
+        (syn::GenericParam::Lifetime(_), value) => Err(syn::Error::new_spanned(value, "expected a lifetime")),
+        (syn::GenericParam::Type(_), value) => Err(syn::Error::new_spanned(value, "expected a type")),
+        (syn::GenericParam::Const(_), value) => Err(syn::Error::new_spanned(value, "expected a constant")),
+    }
+}
+
+/// Replaces every use of a substituted parameter in `input`, e.g. in field types, variants and where predicates.
+pub fn substitute(substitutions: &[Substitution], input: &mut syn::DeriveInput) {
+    Substitute(substitutions).visit_derive_input_mut(input);
+}
+
+/// Replaces every use of a substituted parameter in `generics`, which no longer declare them.
+pub fn substitute_generics(substitutions: &[Substitution], generics: &mut syn::Generics) {
+    let substitutions = &in_conversions(substitutions);
+    generics.params = std::mem::take(&mut generics.params).into_iter()
+        .filter(|param| !substitutions.iter().any(|substitution| &substitution.ident == param_ident(param)))
+        .collect();
+    Substitute(substitutions).visit_generics_mut(generics);
+}
+
+/// A field type as generated conversions see it, with substituted parameters replaced by their value
+/// and renamed ones named like in the source.
+pub fn in_conversion(substitutions: &[Substitution], ty: &syn::Type) -> syn::Type {
+    let mut ty = ty.clone();
+    Substitute(&in_conversions(substitutions)).visit_type_mut(&mut ty);
+    ty
+}
+
+/// The arguments a type with `generics` is used with, substituted parameters given by their value.
+pub fn arguments(generics: &syn::Generics, substitutions: &[Substitution]) -> TokenStream {
+    let substitutions = &in_conversions(substitutions);
+    if generics.params.is_empty() {
+        return TokenStream::new();
+    }
+
+    let arguments = generics.params.iter().map(|param| {
+        let ident = param_ident(param);
+        match (param, substitutions.iter().find(|substitution| &substitution.ident == ident)) {
+            (_, Some(substitution)) => substitution.value.to_token_stream(),
+            (syn::GenericParam::Lifetime(param), None) => param.lifetime.to_token_stream(),
+            (_, None) => ident.to_token_stream(),
+        }
+    });
+    quote! { <#(#arguments),*> }
+}
+
+struct Substitute<'a>(&'a [Substitution]);
+
+impl Substitute<'_> {
+    fn find(&self, ident: &syn::Ident) -> Option<&syn::GenericArgument> {
+        self.0.iter()
+            .find(|substitution| &substitution.ident == ident)
+            .map(|substitution| &substitution.value)
+    }
+
+    /// The substitution of a path which is nothing but the name of a parameter.
+    fn path(&self, qself: &Option<syn::QSelf>, path: &syn::Path) -> Option<&syn::GenericArgument> {
+        match (qself, path.get_ident()) {
+            (None, Some(ident)) => self.find(ident),
+            _ => None,
+        }
+    }
+}
+
+impl VisitMut for Substitute<'_> {
+    fn visit_type_mut(&mut self, ty: &mut syn::Type) {
+        if let syn::Type::Path(path) = ty {
+            if let Some(syn::GenericArgument::Type(value)) = self.path(&path.qself, &path.path) {
+                *ty = value.clone();
+                return;
+            }
+        }
+        syn::visit_mut::visit_type_mut(self, ty);
+    }
+
+    fn visit_generic_argument_mut(&mut self, argument: &mut syn::GenericArgument) {
+        // A const parameter passed on like `Buffer<N>` is parsed as a type.
+        if let syn::GenericArgument::Type(syn::Type::Path(path)) = argument {
+            if let Some(value) = self.path(&path.qself, &path.path) {
+                *argument = value.clone();
+                return;
+            }
+        }
+        syn::visit_mut::visit_generic_argument_mut(self, argument);
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut syn::Expr) {
+        if let syn::Expr::Path(path) = expr {
+            if let Some(syn::GenericArgument::Const(value)) = self.path(&path.qself, &path.path) {
+                *expr = value.clone();
+                return;
+            }
+        }
+        syn::visit_mut::visit_expr_mut(self, expr);
+    }
+
+    fn visit_lifetime_mut(&mut self, lifetime: &mut syn::Lifetime) {
+        if let Some(syn::GenericArgument::Lifetime(value)) = self.find(&lifetime.ident) {
+            *lifetime = value.clone();
+        }
+    }
+}
+
+/// Brings the parameters into canonical order, keeping the order of parameters of the same kind.
+fn sort(generics: &mut syn::Generics) {
+    let mut params = std::mem::take(&mut generics.params).into_iter().collect::<Vec<_>>();
+    params.sort_by_key(kind);
+    generics.params = params.into_iter().collect();
+}
+
+/// The rank of a parameter in canonical order, lifetimes before types before consts.
+fn kind(param: &syn::GenericParam) -> u8 {
+    match param {
+        syn::GenericParam::Lifetime(_) => 0,
+        syn::GenericParam::Type(_) => 1,
+        syn::GenericParam::Const(_) => 2,
+    }
+}
+
+/// The name of a parameter, lifetimes without their `'`.
+pub fn param_ident(param: &syn::GenericParam) -> &syn::Ident {
+    match param {
+        syn::GenericParam::Lifetime(param) => &param.lifetime.ident,
+        syn::GenericParam::Type(param) => &param.ident,
+        syn::GenericParam::Const(param) => &param.ident,
+    }
+}
+
+/// Whether `tokens` refer to the parameter `ident`, either as a type, a const or a lifetime.
+pub fn mentions(tokens: TokenStream, ident: &syn::Ident) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(token) => &token == ident,
+        TokenTree::Group(group) => mentions(group.stream(), ident),
+        _ => false,
+    })
+}
+
+/// Overwrites the parts of `param` which are written in `change`.
+fn change_param(param: &mut syn::GenericParam, change: syn::GenericParam) -> syn::Result<()> {
+    match (param, change) {
+        (syn::GenericParam::Lifetime(param), syn::GenericParam::Lifetime(change)) => {
+            if change.colon_token.is_some() {
+                param.colon_token = change.colon_token;
+                param.bounds = change.bounds;
+            }
+        },
+        (syn::GenericParam::Type(param), syn::GenericParam::Type(change)) => {
+            if change.colon_token.is_some() {
+                param.colon_token = change.colon_token;
+                param.bounds = change.bounds;
+            }
+            if change.eq_token.is_some() {
+                param.eq_token = change.eq_token;
+                param.default = change.default;
+            }
+        },
+        (syn::GenericParam::Const(param), syn::GenericParam::Const(change)) => {
+            param.ty = change.ty;
+            if change.eq_token.is_some() {
+                param.eq_token = change.eq_token;
+                param.default = change.default;
+            }
+        },
+        (_, change) => {
+            return Err(syn::Error::new_spanned(change, "Cannot change the kind of a generic parameter"));
+        },
+    }
+
+    Ok(())
+}
+
+/// Removes the bounds of `removal` from the predicates of the same type or lifetime.
+fn remove_predicate(generics: &mut syn::Generics, removal: syn::WherePredicate) -> syn::Result<()> {
+    let Some(where_clause) = &mut generics.where_clause else {
+        return Err(syn::Error::new_spanned(removal, "No where predicate for this type"));
+    };
+
+    let (bounded, bounds) = match &removal {
+        syn::WherePredicate::Type(predicate) => (
+            predicate.bounded_ty.to_token_stream().to_string(),
+            predicate.bounds.iter().map(|bound| bound.to_token_stream().to_string()).collect::<Vec<_>>(),
+        ),
+        syn::WherePredicate::Lifetime(predicate) => (
+            predicate.lifetime.to_token_stream().to_string(),
+            predicate.bounds.iter().map(|bound| bound.to_token_stream().to_string()).collect::<Vec<_>>(),
+        ),
+        _ => return Err(syn::Error::new_spanned(removal, "unsupported where predicate")),
+    };
+
+    let mut removed = vec![false; bounds.len()];
+    let retain = |existing: String, found: &mut [bool]| {
+        match bounds.iter().position(|bound| bound == &existing) {
+            Some(index) => {
+                found[index] = true;
+                false
+            },
+            None => true,
+        }
+    };
+    for predicate in where_clause.predicates.iter_mut() {
+        match predicate {
+            syn::WherePredicate::Type(predicate) if predicate.bounded_ty.to_token_stream().to_string() == bounded => {
+                predicate.bounds = std::mem::take(&mut predicate.bounds).into_iter()
+                    .filter(|bound| retain(bound.to_token_stream().to_string(), &mut removed))
+                    .collect::<Punctuated<_, _>>();
+            },
+            syn::WherePredicate::Lifetime(predicate) if predicate.lifetime.to_token_stream().to_string() == bounded => {
+                predicate.bounds = std::mem::take(&mut predicate.bounds).into_iter()
+                    .filter(|bound| retain(bound.to_token_stream().to_string(), &mut removed))
+                    .collect::<Punctuated<_, _>>();
+            },
+            _ => {},
+        }
+    }
+    where_clause.predicates = std::mem::take(&mut where_clause.predicates).into_iter()
+        .filter(|predicate| match predicate {
+            syn::WherePredicate::Type(predicate) => !predicate.bounds.is_empty(),
+            syn::WherePredicate::Lifetime(predicate) => !predicate.bounds.is_empty(),
+            _ => true,
+        })
+        .collect();
+
+    if removed.contains(&false) {
+        return Err(syn::Error::new_spanned(removal, "No where predicate with this bound"));
+    }
+
+    Ok(())
+}
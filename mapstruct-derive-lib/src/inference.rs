@@ -0,0 +1,122 @@
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+
+/// A conversion for an obvious retype, which does not need a user supplied converter.
+pub enum Inference {
+    /// `String -> &str` and `Vec<T> -> &[T]`, written as `&*value`.
+    Deref,
+    /// `T -> &T`, written as `&value`.
+    Borrow,
+    /// `T -> Option<T>`, written as `Some(value)`.
+    /// With an inner rule the value is converted first, e.g. `T -> Option<Box<T>>`.
+    Some(Option<Box<Inference>>),
+    /// `T -> Box<T>`, `Rc<T>` or `Arc<T>`, written as `Box::new(value)`.
+    /// With an inner rule the value is converted first, e.g. `String -> Box<&str>`.
+    New(TokenStream, Option<Box<Inference>>),
+    /// `Id(T) -> T` for a newtype declared with `newtype Id(T)`, written as `value.0`.
+    Newtype,
+}
+
+impl Inference {
+    /// Finds the rule converting `source` into `target`, if there is one.
+    /// Only the declared `newtypes`, given with the type they wrap, are unwrapped.
+    pub fn infer(source: &syn::Type, target: &syn::Type, newtypes: &[(&syn::Path, &syn::Type)]) -> Option<Self> {
+        if let syn::Type::Reference(reference) = target {
+            if reference.mutability.is_some() {
+                return None;
+            }
+
+            return match (&*reference.elem, single_argument(source)) {
+                (syn::Type::Path(elem), _) if elem.path.is_ident("str") && is_path(source, "String") => {
+                    Some(Inference::Deref)
+                },
+                (syn::Type::Slice(slice), Some(("Vec", item))) if same(&slice.elem, item) => {
+                    Some(Inference::Deref)
+                },
+                (elem, _) if same(elem, source) => Some(Inference::Borrow),
+                _ => None,
+            };
+        }
+
+        let wrap = |inner: &syn::Type| if same(inner, source) {
+            Some(None)
+        } else {
+            Inference::infer(source, inner, newtypes).map(|inference| Some(Box::new(inference)))
+        };
+        let wrapped = match single_argument(target) {
+            Some(("Option", inner)) => wrap(inner).map(Inference::Some),
+            Some(("Box", inner)) => wrap(inner).map(|inner| Inference::New(quote! { ::std::boxed::Box::new }, inner)),
+            Some(("Rc", inner)) => wrap(inner).map(|inner| Inference::New(quote! { ::std::rc::Rc::new }, inner)),
+            Some(("Arc", inner)) => wrap(inner).map(|inner| Inference::New(quote! { ::std::sync::Arc::new }, inner)),
+            _ => None,
+        };
+        if wrapped.is_some() {
+            return wrapped;
+        }
+
+        newtypes.iter()
+            .any(|(path, inner)| path.to_token_stream().to_string() == source.to_token_stream().to_string() && same(inner, target))
+            .then_some(Inference::Newtype)
+    }
+
+    /// Whether the result borrows from the source, which therefore must not be consumed.
+    pub fn borrows(&self) -> bool {
+        match self {
+            Inference::Deref | Inference::Borrow => true,
+            Inference::Some(Some(inner)) | Inference::New(_, Some(inner)) => inner.borrows(),
+            _ => false,
+        }
+    }
+
+    /// Applies the rule to the field at `place`, cloning owned values out of a borrowed source.
+    pub fn apply(&self, place: TokenStream, borrowed: bool) -> TokenStream {
+        let value = |place: TokenStream| if borrowed {
+            quote! { ::core::clone::Clone::clone(&#place) }
+        } else {
+            place
+        };
+
+        match self {
+            Inference::Deref => quote! { &*#place },
+            Inference::Borrow => quote! { &#place },
+            Inference::Some(inner) => {
+                let value = inner.as_ref().map_or_else(|| value(place.clone()), |inner| inner.apply(place.clone(), borrowed));
+                quote! { ::core::option::Option::Some(#value) }
+            },
+            Inference::New(constructor, inner) => {
+                let value = inner.as_ref().map_or_else(|| value(place.clone()), |inner| inner.apply(place.clone(), borrowed));
+                quote! { #constructor(#value) }
+            },
+            Inference::Newtype => value(quote! { #place.0 }),
+        }
+    }
+}
+
+/// Splits `Wrapper<T>` into the name of the wrapper and `T`.
+fn single_argument(ty: &syn::Type) -> Option<(&str, &syn::Type)> {
+    let syn::Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+    if arguments.args.len() != 1 {
+        return None;
+    }
+    let syn::GenericArgument::Type(argument) = arguments.args.first()? else {
+        return None;
+    };
+
+    ["Option", "Box", "Rc", "Arc", "Vec"].into_iter()
+        .find(|name| segment.ident == name)
+        .map(|name| (name, argument))
+}
+
+fn is_path(ty: &syn::Type, name: &str) -> bool {
+    matches!(ty, syn::Type::Path(path) if path.qself.is_none() && path.path.is_ident(name))
+}
+
+fn same(a: &syn::Type, b: &syn::Type) -> bool {
+    a.to_token_stream().to_string() == b.to_token_stream().to_string()
+}
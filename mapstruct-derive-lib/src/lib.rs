@@ -15,6 +15,16 @@ mod struct_change;
 mod enum_change;
 mod tuple_change;
 mod unnamed_field_change;
+mod directive;
+mod conversion;
+mod converter;
+mod inference;
+mod attribute_change;
+mod shape_change;
+mod selector;
+mod template;
+mod order;
+mod replace;
 
 #[macro_export]
 macro_rules! unwrap_one_variant {
@@ -35,7 +45,7 @@ pub fn derive(input: TokenStream) -> TokenStream {
     }
 }
 
-fn syn_derive(input: TokenStream) -> syn::Result<Vec<DeriveInput>> {
+fn syn_derive(input: TokenStream) -> syn::Result<Vec<TokenStream>> {
     let input = parse2::<DeriveInput>(input)?;
     let attrs = input.attrs
         .iter()
@@ -112,6 +122,1675 @@ mod tests {
         assert_eq!(expected.to_string(), derive(input).to_string());
     }
 
+    #[test]
+    fn test_derive_struct_from() {
+        let input = quote! {
+            #[mapstruct(
+                from,
+                #[derive(Debug)]
+                struct Y {
+                    ~id -> x_id,
+                    -height,
+                }
+            )]
+            struct X<T> {
+                id: i64,
+                name: T,
+                height: f32,
+            }
+        };
+        let expected = quote! {
+            #[derive(Debug)]
+            struct Y<T> {
+                x_id: i64,
+                name: T
+            }
+            impl<T> ::core::convert::From<X<T> > for Y<T> {
+                fn from(src: X<T>) -> Self {
+                    Self {
+                        x_id: src.id,
+                        name: src.name
+                    }
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+    }
+
+    #[test]
+    fn test_derive_struct_from_computed() {
+        let input = quote! {
+            #[mapstruct(
+                from,
+                struct Y {
+                    +full_name: String = format!("{} {}", src.first, src.last),
+                }
+            )]
+            struct X {
+                first: String,
+                last: String,
+            }
+        };
+        let expected = quote! {
+            struct Y {
+                first: String,
+                last: String,
+                full_name: String
+            }
+            impl ::core::convert::From<X> for Y {
+                fn from(src: X) -> Self {
+                    let full_name = format!("{} {}", src.first, src.last);
+                    Self {
+                        first: src.first,
+                        last: src.last,
+                        full_name: full_name
+                    }
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+    }
+
+    #[test]
+    fn test_derive_struct_from_with() {
+        let input = quote! {
+            #[mapstruct(
+                from,
+                struct Y {
+                    ~created: i64 with chrono_to_unix,
+                    ~name -> upper_name with |name: String| name.to_uppercase(),
+                }
+            )]
+            struct X {
+                created: DateTime,
+                name: String,
+            }
+        };
+        let expected = quote! {
+            struct Y {
+                created: i64,
+                upper_name: String
+            }
+            impl ::core::convert::From<X> for Y {
+                fn from(src: X) -> Self {
+                    Self {
+                        created: chrono_to_unix(src.created),
+                        upper_name: (|name: String| name.to_uppercase())(src.name)
+                    }
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+    }
+
+    #[test]
+    fn test_derive_struct_reverse() {
+        let input = quote! {
+            #[mapstruct(
+                reverse,
+                struct Y {
+                    ~id -> x_id,
+                    -height = 1.8,
+                    +note: String,
+                }
+            )]
+            struct X {
+                id: i64,
+                height: f32,
+                name: String,
+            }
+        };
+        let expected = quote! {
+            struct Y {
+                x_id: i64,
+                name: String,
+                note: String
+            }
+            impl ::core::convert::From<Y> for X {
+                fn from(src: Y) -> Self {
+                    let height = 1.8;
+                    Self {
+                        id: src.x_id,
+                        height: height,
+                        name: src.name
+                    }
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+    }
+
+    #[test]
+    fn test_derive_struct_reverse_missing_default() {
+        let input = quote! {
+            #[mapstruct(
+                reverse,
+                struct Y {
+                    -height,
+                }
+            )]
+            struct X {
+                id: i64,
+                height: f32,
+            }
+        };
+        let output = derive(input).to_string();
+        assert!(output.contains("removed field `height` needs a default"), "{}", output);
+    }
+
+    #[test]
+    fn test_derive_struct_reverse_retyped() {
+        let input = quote! {
+            #[mapstruct(
+                reverse,
+                struct Y {
+                    ~height: f64 with f64::from back |height| height as f32,
+                }
+            )]
+            struct X {
+                height: f32,
+            }
+        };
+        let expected = quote! {
+            struct Y {
+                height: f64
+            }
+            impl ::core::convert::From<Y> for X {
+                fn from(src: Y) -> Self {
+                    Self {
+                        height: (|height| height as f32)(src.height)
+                    }
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+
+        let input = quote! {
+            #[mapstruct(
+                reverse,
+                struct Y {
+                    ~height: Option<f32>,
+                }
+            )]
+            struct X {
+                height: f32,
+            }
+        };
+        let output = derive(input).to_string();
+        assert!(output.contains("field `height` is retyped, convert it back with `with <expr> back <expr>`"), "{}", output);
+    }
+
+    #[test]
+    fn test_derive_struct_try_from() {
+        let input = quote! {
+            #[mapstruct(
+                try_from,
+                pub struct Y {
+                    ~age: u32,
+                    ~email: Email try with Email::parse: ParseError,
+                    ~id: i64 with i64::from,
+                }
+            )]
+            struct X {
+                age: i64,
+                email: String,
+                id: i32,
+            }
+        };
+        let expected = quote! {
+            pub struct Y {
+                age: u32,
+                email: Email,
+                id: i64
+            }
+            pub enum YConversionError {
+                Age(<u32 as ::core::convert::TryFrom<i64> >::Error),
+                Email(ParseError)
+            }
+            impl ::core::fmt::Debug for YConversionError {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    match *self {
+                        YConversionError::Age(ref error) => f.debug_tuple("Age").field(error).finish(),
+                        YConversionError::Email(ref error) => f.debug_tuple("Email").field(error).finish(),
+                    }
+                }
+            }
+            impl ::core::fmt::Display for YConversionError {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    match *self {
+                        YConversionError::Age(ref error) => ::core::write!(f, "failed to convert field `age`: {}", error),
+                        YConversionError::Email(ref error) => ::core::write!(f, "failed to convert field `email`: {}", error),
+                    }
+                }
+            }
+            impl ::std::error::Error for YConversionError {}
+            impl ::core::convert::TryFrom<X> for Y {
+                type Error = YConversionError;
+
+                fn try_from(src: X) -> ::core::result::Result<Self, Self::Error> {
+                    ::core::result::Result::Ok({
+                        Self {
+                            age: ::core::convert::TryFrom::try_from(src.age).map_err(YConversionError::Age)?,
+                            email: Email::parse(src.email).map_err(YConversionError::Email)?,
+                            id: i64::from(src.id)
+                        }
+                    })
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+    }
+
+    #[test]
+    fn test_derive_struct_try_from_infallible() {
+        let input = quote! {
+            #[mapstruct(
+                try_from,
+                struct A2 {
+                    -b,
+                }
+            )]
+            struct A {
+                a: u8,
+                b: u8,
+            }
+        };
+        let expected = quote! {
+            struct A2 {
+                a: u8
+            }
+            enum A2ConversionError {}
+            impl ::core::fmt::Debug for A2ConversionError {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    match *self {}
+                }
+            }
+            impl ::core::fmt::Display for A2ConversionError {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    match *self {}
+                }
+            }
+            impl ::std::error::Error for A2ConversionError {}
+            impl ::core::convert::TryFrom<A> for A2 {
+                type Error = A2ConversionError;
+
+                fn try_from(src: A) -> ::core::result::Result<Self, Self::Error> {
+                    ::core::result::Result::Ok({
+                        Self {
+                            a: src.a
+                        }
+                    })
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+    }
+
+    #[test]
+    fn test_derive_struct_try_from_generic() {
+        let input = quote! {
+            #[mapstruct(
+                try_from,
+                struct Y {
+                    ~a: u8 try with u8::try_from: <u8 as ::core::convert::TryFrom<T> >::Error,
+                }
+            )]
+            struct X<T, U> where u8: TryFrom<T> {
+                a: T,
+                b: U,
+            }
+        };
+        let output = derive(input).to_string();
+        let expected = quote! {
+            enum YConversionError<T> where u8: TryFrom<T> {
+                A(<u8 as ::core::convert::TryFrom<T> >::Error)
+            }
+            impl<T> ::core::fmt::Debug for YConversionError<T>
+            where
+                u8: TryFrom<T>,
+                <u8 as ::core::convert::TryFrom<T> >::Error: ::core::fmt::Debug
+            {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    match *self {
+                        YConversionError::A(ref error) => f.debug_tuple("A").field(error).finish(),
+                    }
+                }
+            }
+        };
+        assert!(output.contains(&expected.to_string()), "{}", output);
+        assert!(output.contains(&quote! { type Error = YConversionError<T>; }.to_string()), "{}", output);
+    }
+
+    #[test]
+    fn test_derive_struct_try_from_substituted() {
+        let input = quote! {
+            #[mapstruct(
+                try_from,
+                struct Y<
+                    ~T => u32,
+                > {
+                    ~x: u8,
+                }
+            )]
+            struct X<T> {
+                x: T,
+            }
+        };
+        let output = derive(input).to_string();
+        let expected = quote! {
+            enum YConversionError {
+                X(<u8 as ::core::convert::TryFrom<u32> >::Error)
+            }
+        };
+        assert!(output.contains(&expected.to_string()), "{}", output);
+        assert!(output.contains(&quote! { TryFrom<X<u32> > for Y }.to_string()), "{}", output);
+    }
+
+    #[test]
+    fn test_derive_struct_try_from_renamed() {
+        let input = quote! {
+            #[mapstruct(
+                try_from,
+                struct Y<
+                    ~T -> Item,
+                > {
+                    ~x: Option<Item>,
+                    ~y: u8,
+                }
+            )]
+            struct X<T> where u8: TryFrom<T> {
+                x: T,
+                y: T,
+            }
+        };
+        let output = derive(input).to_string();
+        let expected = quote! {
+            enum YConversionError<T> where u8: TryFrom<T> {
+                Y(<u8 as ::core::convert::TryFrom<T> >::Error)
+            }
+        };
+        assert!(output.contains(&expected.to_string()), "{}", output);
+        assert!(output.contains(&quote! { x: ::core::option::Option::Some(src.x) }.to_string()), "{}", output);
+    }
+
+    #[test]
+    fn test_derive_struct_from_inferred() {
+        let input = quote! {
+            #[mapstruct(
+                from(&'a),
+                newtype UserId(i64),
+                struct Y<
+                    +'a,
+                > {
+                    ~name: &'a str,
+                    ~tags: &'a [String],
+                    ~age: Option<i32>,
+                    ~parent: Box<X>,
+                    ~id: i64,
+                }
+            )]
+            struct X {
+                name: String,
+                tags: Vec<String>,
+                age: i32,
+                parent: X,
+                id: UserId,
+                height: f32,
+            }
+        };
+        let expected = quote! {
+            struct Y<'a> {
+                name: &'a str,
+                tags: &'a [String],
+                age: Option<i32>,
+                parent: Box<X>,
+                id: i64,
+                height: f32
+            }
+            impl<'a> ::core::convert::From<&'a X> for Y<'a> {
+                fn from(src: &'a X) -> Self {
+                    Self {
+                        name: &*src.name,
+                        tags: &*src.tags,
+                        age: ::core::option::Option::Some(::core::clone::Clone::clone(&src.age)),
+                        parent: ::std::boxed::Box::new(::core::clone::Clone::clone(&src.parent)),
+                        id: ::core::clone::Clone::clone(&src.id.0),
+                        height: ::core::clone::Clone::clone(&src.height)
+                    }
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+    }
+
+    #[test]
+    fn test_derive_struct_from_undeclared_newtype() {
+        let input = quote! {
+            #[mapstruct(
+                from,
+                struct Y {
+                    ~id: String,
+                }
+            )]
+            struct X {
+                id: Uuid,
+            }
+        };
+        let output = derive(input).to_string();
+        assert!(output.contains("cannot infer a conversion of field `id` from `Uuid` to `String`"), "{}", output);
+    }
+
+    #[test]
+    fn test_derive_struct_try_from_generic_not_newtype() {
+        let input = quote! {
+            #[mapstruct(
+                try_from,
+                struct Y {
+                    ~x: u8,
+                }
+            )]
+            struct X<T> {
+                x: T,
+            }
+        };
+        let output = derive(input).to_string();
+        let expected = quote! {
+            x: ::core::convert::TryFrom::try_from(src.x).map_err(YConversionError::X)?
+        };
+        assert!(output.contains(&expected.to_string()), "{}", output);
+        assert!(!output.contains(". 0"), "{}", output);
+    }
+
+    #[test]
+    fn test_derive_struct_from_inferred_borrow() {
+        let input = quote! {
+            #[mapstruct(
+                from,
+                struct Y<
+                    +'a,
+                > {
+                    ~name: &'a str,
+                }
+            )]
+            struct X {
+                name: String,
+            }
+        };
+        let output = derive(input).to_string();
+        assert!(output.contains("field `name` borrows from the source, use `from(&'a)`"), "{}", output);
+    }
+
+    #[test]
+    fn test_derive_struct_from_retyped() {
+        let input = quote! {
+            #[mapstruct(
+                from,
+                struct Y {
+                    ~id: u32,
+                }
+            )]
+            struct X {
+                id: i64,
+            }
+        };
+        let output = derive(input).to_string();
+        assert!(output.contains("cannot infer a conversion of field `id` from `i64` to `u32`"), "{}", output);
+    }
+
+    #[test]
+    fn test_derive_struct_pick() {
+        let input = quote! {
+            #[mapstruct(
+                from,
+                struct Y {
+                    =id,
+                    =name,
+                    ~email: Option<String>,
+                }
+            )]
+            struct X {
+                id: u64,
+                password: String,
+                name: String,
+                email: String,
+            }
+        };
+        let expected = quote! {
+            struct Y {
+                id: u64,
+                name: String,
+                email: Option<String>
+            }
+            impl ::core::convert::From<X> for Y {
+                fn from(src: X) -> Self {
+                    Self {
+                        id: src.id,
+                        name: src.name,
+                        email: ::core::option::Option::Some(src.email)
+                    }
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+
+        let input = quote! {
+            #[mapstruct(
+                struct Y {
+                    =id,
+                    =username,
+                }
+            )]
+            struct X {
+                id: u64,
+                name: String,
+            }
+        };
+        let output = derive(input).to_string();
+        assert!(output.contains("Cannot pick field `username`, the source has no field with this name"), "{}", output);
+    }
+
+    #[test]
+    fn test_derive_struct_patterns() {
+        let input = quote! {
+            #[mapstruct(
+                from,
+                struct Y {
+                    -audit_*,
+                    ~*_at: Option<u64>,
+                    ~pub *,
+                    ~name -> full_name,
+                }
+            )]
+            struct X {
+                id: u64,
+                name: String,
+                audit_user: String,
+                audit_reason: String,
+                created_at: u64,
+                updated_at: u64,
+            }
+        };
+        let expected = quote! {
+            struct Y {
+                pub id: u64,
+                full_name: String,
+                created_at: Option<u64>,
+                updated_at: Option<u64>
+            }
+            impl ::core::convert::From<X> for Y {
+                fn from(src: X) -> Self {
+                    Self {
+                        id: src.id,
+                        full_name: src.name,
+                        created_at: ::core::option::Option::Some(src.created_at),
+                        updated_at: ::core::option::Option::Some(src.updated_at)
+                    }
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+
+        let input = quote! {
+            #[mapstruct(
+                struct Y {
+                    -internal_*,
+                }
+            )]
+            struct X {
+                id: u64,
+            }
+        };
+        let output = derive(input).to_string();
+        assert!(output.contains("No field matches this pattern"), "{}", output);
+
+        let input = quote! {
+            #[mapstruct(
+                struct Y {
+                    ~*_at: Option<_>,
+                    ~*: Box<_>,
+                }
+            )]
+            struct X {
+                pub id: u64,
+                pub(crate) created_at: u64,
+            }
+        };
+        let expected = quote! {
+            struct Y {
+                pub id: Box<u64>,
+                pub(crate) created_at: Option<u64>
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+    }
+
+    #[test]
+    fn test_derive_struct_templates() {
+        let input = quote! {
+            #[mapstruct(
+                from,
+                struct Y {
+                    ~{name, email}: Option<Box<_>>,
+                    ~*: Option<_>,
+                }
+            )]
+            struct X {
+                id: u64,
+                name: String,
+                email: String,
+            }
+        };
+        let expected = quote! {
+            struct Y {
+                id: Option<u64>,
+                name: Option<Box<String> >,
+                email: Option<Box<String> >
+            }
+            impl ::core::convert::From<X> for Y {
+                fn from(src: X) -> Self {
+                    Self {
+                        id: ::core::option::Option::Some(src.id),
+                        name: ::core::option::Option::Some(::std::boxed::Box::new(src.name)),
+                        email: ::core::option::Option::Some(::std::boxed::Box::new(src.email))
+                    }
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+
+        let input = quote! {
+            #[mapstruct(
+                from(&'a),
+                enum Y<
+                    +'a,
+                > {
+                    ~A(*: &'a _),
+                    ~B {
+                        ~*: &'a _,
+                    },
+                }
+            )]
+            enum X {
+                A(u8, String),
+                B {
+                    id: u64,
+                },
+            }
+        };
+        let expected = quote! {
+            enum Y<'a> {
+                A(&'a u8, &'a String),
+                B {
+                    id: &'a u64
+                }
+            }
+            impl<'a> ::core::convert::From<&'a X> for Y<'a> {
+                fn from(src: &'a X) -> Self {
+                    match src {
+                        X::A(field0, field1) => {
+                            Self::A(&(*field0), &(*field1))
+                        },
+                        X::B { id, } => {
+                            Self::B {
+                                id: &(*id)
+                            }
+                        },
+                    }
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+
+        let input = quote! {
+            #[mapstruct(
+                struct Y {
+                    ~{name, email}: Box<_>,
+                }
+            )]
+            struct X {
+                name: String,
+            }
+        };
+        let output = derive(input).to_string();
+        assert!(output.contains("No field with this name"), "{}", output);
+
+        let input = quote! {
+            #[mapstruct(
+                struct Y {
+                    ~{name, email}: Box<_>,
+                    ~{id} -> pub(crate) key,
+                }
+            )]
+            struct X {
+                pub id: u64,
+                pub name: String,
+                pub(crate) email: String,
+            }
+        };
+        let expected = quote! {
+            struct Y {
+                pub(crate) key: u64,
+                pub name: Box<String>,
+                pub(crate) email: Box<String>
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+    }
+
+    #[test]
+    fn test_derive_struct_positions() {
+        let input = quote! {
+            #[mapstruct(
+                struct Y {
+                    +before(id) tenant: u64,
+                    +after(id) #[serde(skip)] revision: u32,
+                }
+            )]
+            #[mapstruct(
+                from,
+                order(name, id),
+                struct Z {
+                    -age,
+                }
+            )]
+            struct X {
+                id: u64,
+                name: String,
+                age: u8,
+            }
+        };
+        let expected = quote! {
+            struct Y {
+                tenant: u64,
+                id: u64,
+                #[serde(skip)]
+                revision: u32,
+                name: String,
+                age: u8
+            }
+            struct Z {
+                name: String,
+                id: u64
+            }
+            impl ::core::convert::From<X> for Z {
+                fn from(src: X) -> Self {
+                    Self {
+                        name: src.name,
+                        id: src.id
+                    }
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+
+        let input = quote! {
+            #[mapstruct(
+                order(name),
+                struct Y {
+                    -age,
+                }
+            )]
+            struct X {
+                id: u64,
+                name: String,
+                age: u8,
+            }
+        };
+        let output = derive(input).to_string();
+        assert!(output.contains("`order` has to list every field or variant, `id` is missing"), "{}", output);
+    }
+
+    #[test]
+    fn test_derive_struct_where_clause() {
+        let input = quote! {
+            #[mapstruct(
+                struct Y<
+                    -T,
+                    +where U: Sync,
+                    -where U: Clone,
+                > {
+                    -t,
+                }
+            )]
+            struct X<T, U> where T: Clone, U: Clone + Send, Vec<T>: Default {
+                t: T,
+                u: U,
+            }
+        };
+        let expected = quote! {
+            struct Y<U> where U: Send, U: Sync {
+                u: U
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+
+        let input = quote! {
+            #[mapstruct(
+                struct Y<
+                    -where T: Send,
+                > {}
+            )]
+            struct X<T> where T: Clone {
+                t: T,
+            }
+        };
+        let output = derive(input).to_string();
+        assert!(output.contains("No where predicate with this bound"), "{}", output);
+    }
+
+    #[test]
+    fn test_derive_struct_change_generics() {
+        let input = quote! {
+            #[mapstruct(
+                from,
+                struct Y<
+                    ~'b: 'a,
+                    ~T: Clone + Send,
+                    ~U = String,
+                    ~const N: usize = 8,
+                > {}
+            )]
+            struct X<'a, 'b, T: Clone, U, const N: usize> {
+                a: &'a T,
+                b: &'b [U; N],
+            }
+        };
+        let expected = quote! {
+            struct Y<'a, 'b: 'a, T: Clone + Send, U = String, const N: usize = 8> {
+                a: &'a T,
+                b: &'b [U; N]
+            }
+
+            impl<'a, 'b: 'a, T: Clone + Send, U, const N: usize> ::core::convert::From<X<'a, 'b, T, U, N> > for Y<'a, 'b, T, U, N> {
+                fn from(src: X<'a, 'b, T, U, N>) -> Self {
+                    Self {
+                        a: src.a,
+                        b: src.b
+                    }
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+
+        let input = quote! {
+            #[mapstruct(
+                struct Y<
+                    ~V: Clone,
+                > {}
+            )]
+            struct X<T> {
+                t: T,
+            }
+        };
+        let output = derive(input).to_string();
+        assert!(output.contains("No generic parameter with this name"), "{}", output);
+    }
+
+    #[test]
+    fn test_derive_struct_substitute_generics() {
+        let input = quote! {
+            #[mapstruct(
+                from,
+                struct Y<
+                    ~'a => 'static,
+                    ~T => String,
+                    ~N => 16,
+                > {}
+            )]
+            struct X<'a, T, U, const N: usize> where T: Clone, U: From<T> {
+                name: &'a str,
+                items: Vec<T>,
+                buffer: [U; N],
+                nested: Buffer<N>,
+            }
+        };
+        let expected = quote! {
+            struct Y<U> where String: Clone, U: From<String> {
+                name: &'static str,
+                items: Vec<String>,
+                buffer: [U; 16],
+                nested: Buffer<16>
+            }
+
+            impl<U> ::core::convert::From<X<'static, String, U, 16> > for Y<U> where String: Clone, U: From<String> {
+                fn from(src: X<'static, String, U, 16>) -> Self {
+                    Self {
+                        name: src.name,
+                        items: src.items,
+                        buffer: src.buffer,
+                        nested: src.nested
+                    }
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+
+        let input = quote! {
+            #[mapstruct(
+                struct Y<
+                    ~T => 16,
+                > {}
+            )]
+            struct X<T> {
+                t: T,
+            }
+        };
+        let output = derive(input).to_string();
+        assert!(output.contains("expected a type"), "{}", output);
+    }
+
+    #[test]
+    fn test_derive_struct_unused_generics() {
+        let input = quote! {
+            #[mapstruct(
+                struct Y {
+                    -u,
+                }
+            )]
+            struct X<'a, T, U: Into<T>> where U: Clone {
+                name: &'a str,
+                t: T,
+                u: U,
+            }
+        };
+        let expected = quote! {
+            struct Y<'a, T> {
+                name: &'a str,
+                t: T
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+
+        let input = quote! {
+            #[mapstruct(
+                from,
+                phantom,
+                struct Y {
+                    -t,
+                }
+            )]
+            struct X<'a, T> {
+                name: &'a str,
+                t: T,
+            }
+        };
+        let expected = quote! {
+            struct Y<'a, T> {
+                name: &'a str,
+                _phantom: ::core::marker::PhantomData<(T,)>
+            }
+
+            impl<'a, T> ::core::convert::From<X<'a, T> > for Y<'a, T> {
+                fn from(src: X<'a, T>) -> Self {
+                    let _phantom = ::core::marker::PhantomData;
+                    Self {
+                        name: src.name,
+                        _phantom: _phantom
+                    }
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+
+        let input = quote! {
+            #[mapstruct(
+                struct Y<
+                    -'a,
+                > {}
+            )]
+            struct X<'a> {
+                name: &'a str,
+            }
+        };
+        let output = derive(input).to_string();
+        assert!(output.contains("Generic parameter `'a` is removed but still used by this field"), "{}", output);
+    }
+
+    #[test]
+    fn test_derive_enum_phantom() {
+        let input = quote! {
+            #[mapstruct(
+                reverse,
+                phantom,
+                enum Y {
+                    -B,
+                }
+            )]
+            enum X<T> {
+                A(u8),
+                B(T),
+            }
+        };
+        let expected = quote! {
+            enum Y<T> {
+                A(u8),
+                #[doc(hidden)]
+                _Phantom(::core::convert::Infallible, ::core::marker::PhantomData<(T,)>)
+            }
+            impl<T> ::core::convert::From<Y<T> > for X<T> {
+                fn from(src: Y<T>) -> Self {
+                    match src {
+                        Y::A(field0) => { Self::A(field0) },
+                        Y::_Phantom(never, _) => match never {},
+                    }
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+    }
+
+    #[test]
+    fn test_derive_struct_infer_lifetimes() {
+        let input = quote! {
+            #[mapstruct(
+                struct Y {
+                    ~name: &str,
+                    +label: &'b str,
+                    +code: &'static str,
+                    +parse: fn(&str) -> T,
+                }
+            )]
+            struct X<T> {
+                name: String,
+                value: T,
+            }
+        };
+        let expected = quote! {
+            struct Y<'a, 'b, T> {
+                name: &'a str,
+                value: T,
+                label: &'b str,
+                code: &'static str,
+                parse: fn(&str) -> T
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+
+        let input = quote! {
+            #[mapstruct(
+                from(&'src),
+                struct Y {
+                    ~name: &str,
+                }
+            )]
+            struct X<'a> {
+                name: String,
+                other: &'a str,
+            }
+        };
+        let expected = quote! {
+            struct Y<'a, 'src> {
+                name: &'src str,
+                other: &'a str
+            }
+
+            impl<'a, 'src> ::core::convert::From<&'src X<'a> > for Y<'a, 'src> {
+                fn from(src: &'src X<'a>) -> Self {
+                    Self {
+                        name: &*src.name,
+                        other: ::core::clone::Clone::clone(&src.other)
+                    }
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+
+        let lifetimes = ('a'..='z')
+            .map(|name| syn::Lifetime::new(&format!("'{}", name), proc_macro2::Span::call_site()))
+            .collect::<Vec<_>>();
+        let input = quote! {
+            #[mapstruct(
+                struct Y {
+                    ~name: &str,
+                }
+            )]
+            struct X<#(#lifetimes),*> {
+                name: String,
+                other: (#(&#lifetimes str),*),
+            }
+        };
+        let expected = quote! {
+            struct Y<#(#lifetimes,)* '__mapstruct0> {
+                name: &'__mapstruct0 str,
+                other: (#(&#lifetimes str),*)
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+    }
+
+    #[test]
+    fn test_derive_struct_generic_order() {
+        let input = quote! {
+            #[mapstruct(
+                struct Y<
+                    +'a,
+                    +S before T,
+                    +'b after 'a,
+                    +U: Clone after T,
+                > {
+                    +name: &'a str,
+                    +other: &'b [S; N],
+                    +u: U,
+                }
+            )]
+            struct X<T, const N: usize> {
+                t: [T; N],
+            }
+        };
+        let expected = quote! {
+            struct Y<'a, 'b, S, T, U: Clone, const N: usize> {
+                t: [T; N],
+                name: &'a str,
+                other: &'b [S; N],
+                u: U
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+
+        let input = quote! {
+            #[mapstruct(
+                struct Y<
+                    +before(T) 'a,
+                    +after(T) const M: usize,
+                    +before(T) const L: usize,
+                > {}
+            )]
+            struct X<T> {
+                t: T,
+            }
+        };
+        let output = derive(input).to_string();
+        assert!(output.contains("Lifetimes have to come before types and types before consts"), "{}", output);
+
+        let input = quote! {
+            #[mapstruct(
+                struct Y<
+                    +before(T) S after T,
+                > {}
+            )]
+            struct X<T> {
+                t: T,
+            }
+        };
+        let output = derive(input).to_string();
+        assert!(output.contains("the position is already given in front of the parameter"), "{}", output);
+    }
+
+    #[test]
+    fn test_derive_struct_rename_generics() {
+        let input = quote! {
+            #[mapstruct(
+                from,
+                struct Y<
+                    ~'a -> 'src,
+                    ~T -> Item,
+                    ~N -> LEN,
+                > {}
+            )]
+            struct X<'a, T: Clone + 'a, const N: usize> where T: Default {
+                name: &'a str,
+                items: [T; N],
+                nested: Buffer<N>,
+            }
+        };
+        let expected = quote! {
+            struct Y<'src, Item: Clone + 'src, const LEN: usize> where Item: Default {
+                name: &'src str,
+                items: [Item; LEN],
+                nested: Buffer<LEN>
+            }
+
+            impl<'a, T: Clone + 'a, const N: usize> ::core::convert::From<X<'a, T, N> > for Y<'a, T, N> where T: Default {
+                fn from(src: X<'a, T, N>) -> Self {
+                    Self {
+                        name: src.name,
+                        items: src.items,
+                        nested: src.nested
+                    }
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+
+        let input = quote! {
+            #[mapstruct(
+                struct Y<
+                    ~T -> U,
+                > {}
+            )]
+            struct X<T, U> {
+                t: T,
+                u: U,
+            }
+        };
+        let output = derive(input).to_string();
+        assert!(output.contains("A generic parameter with this name exists already"), "{}", output);
+    }
+
+    #[test]
+    fn test_derive_struct_replace() {
+        let input = quote! {
+            #[mapstruct(
+                from,
+                replace String => Arc<str> with Arc::from,
+                struct Y {}
+            )]
+            struct X {
+                id: u64,
+                name: String,
+            }
+        };
+        let expected = quote! {
+            struct Y {
+                id: u64,
+                name: Arc<str>
+            }
+
+            impl ::core::convert::From<X> for Y {
+                fn from(src: X) -> Self {
+                    Self {
+                        id: src.id,
+                        name: Arc::from(src.name)
+                    }
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+
+        let input = quote! {
+            #[mapstruct(
+                replace String => Arc<str>,
+                replace chrono::DateTime<Utc> => i64,
+                struct Y(_, _, +Option<String>);
+            )]
+            struct X(Vec<String>, HashMap<String, chrono::DateTime<Utc> >);
+        };
+        let expected = quote! {
+            struct Y(Vec<Arc<str> >, HashMap<Arc<str>, i64>, Option<String>);
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+
+        let input = quote! {
+            #[mapstruct(
+                replace Uuid => String,
+                struct Y {}
+            )]
+            struct X {
+                id: u64,
+            }
+        };
+        let output = derive(input).to_string();
+        assert!(output.contains("Nothing was replaced, no field uses this type"), "{}", output);
+
+        let input = quote! {
+            #[mapstruct(
+                from,
+                replace String => Arc<str> with Arc::from,
+                struct Y {
+                    ~name: String with keep,
+                    +label: String = String::new(),
+                }
+            )]
+            struct X {
+                name: String,
+                email: String,
+            }
+        };
+        let expected = quote! {
+            struct Y {
+                name: String,
+                email: Arc<str>,
+                label: String
+            }
+
+            impl ::core::convert::From<X> for Y {
+                fn from(src: X) -> Self {
+                    let label = String::new();
+                    Self {
+                        name: keep(src.name),
+                        email: Arc::from(src.email),
+                        label: label
+                    }
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+    }
+
+    #[test]
+    fn test_derive_enum_replace() {
+        let input = quote! {
+            #[mapstruct(
+                replace String => Box<str>,
+                enum Y {
+                    +C(Option<String>),
+                }
+            )]
+            enum X {
+                A(String),
+                B {
+                    names: Vec<String>,
+                },
+            }
+        };
+        let expected = quote! {
+            enum Y {
+                A(Box<str>),
+                B {
+                    names: Vec<Box<str> >,
+                },
+                C(Option<String>)
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+    }
+
+    #[test]
+    fn test_derive_struct_field_attributes() {
+        let input = quote! {
+            #[mapstruct(
+                struct Y {
+                    ~id #[-serde] #[+serde(skip)],
+                    ~name -> full_name: &'static str #[+serde(borrow)],
+                    +#[serde(rename = "userId")] user_id: u64,
+                }
+            )]
+            struct X {
+                #[serde(rename = "ID")]
+                #[doc = "The id"]
+                id: u64,
+                name: String,
+            }
+        };
+        let expected = quote! {
+            struct Y {
+                #[doc = "The id"]
+                #[serde(skip)]
+                id: u64,
+                #[serde(borrow)]
+                full_name: &'static str,
+                #[serde(rename = "userId")]
+                user_id: u64
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+
+        let input = quote! {
+            #[mapstruct(
+                struct Y {
+                    ~id #[-serde],
+                }
+            )]
+            struct X {
+                id: u64,
+            }
+        };
+        let output = derive(input).to_string();
+        assert!(output.contains("No attribute with this path"), "{}", output);
+    }
+
+    #[test]
+    fn test_derive_struct_inherit_missing_entry() {
+        let input = quote! {
+            #[mapstruct(
+                inherit,
+                -#[derive(Hash)]
+                struct Y {
+                    -secret,
+                }
+            )]
+            #[derive(Debug)]
+            struct X {
+                id: u64,
+                secret: u64,
+            }
+        };
+        let output = derive(input).to_string();
+        assert!(output.contains("No attribute with this entry"), "{}", output);
+    }
+
+    #[test]
+    fn test_derive_tuple_struct() {
+        let input = quote! {
+            #[mapstruct(
+                from(&'a),
+                reverse,
+                #[derive(Debug)]
+                struct Y<
+                    +'a,
+                >(_, ~&'a str, -f32 = 0.0, +u8 = 7);
+            )]
+            struct X(i64, String, f32);
+        };
+        let output = derive(input).to_string();
+        assert!(output.contains("cannot generate a reverse conversion, field `1` is retyped"), "{}", output);
+
+        let input = quote! {
+            #[mapstruct(
+                from(&'a),
+                #[derive(Debug)]
+                struct Y<
+                    +'a,
+                >(_, ~&'a str, -f32, +u8 = 7);
+            )]
+            struct X(i64, String, f32);
+        };
+        let expected = quote! {
+            #[derive(Debug)]
+            struct Y<'a>(i64, &'a str, u8);
+            impl<'a> ::core::convert::From<&'a X> for Y<'a> {
+                fn from(src: &'a X) -> Self {
+                    let value2 = 7;
+                    Self(::core::clone::Clone::clone(&src.0), &*src.1, value2)
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+
+        let input = quote! {
+            #[mapstruct(
+                struct Y(+1: #[serde(skip)] pub u8);
+            )]
+            struct X(i64, String);
+        };
+        let expected = quote! {
+            struct Y(i64, #[serde(skip)] pub u8, String);
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+    }
+
+    #[test]
+    fn test_derive_unit_struct() {
+        let input = quote! {
+            #[mapstruct(
+                from,
+                #[derive(Debug)]
+                struct Locked;
+            )]
+            #[mapstruct(
+                from,
+                struct Y {
+                    +id: u64 = 0,
+                }
+            )]
+            struct Open;
+        };
+        let expected = quote! {
+            #[derive(Debug)]
+            struct Locked;
+            impl ::core::convert::From<Open> for Locked {
+                fn from(src: Open) -> Self {
+                    Self
+                }
+            }
+            struct Y {
+                id: u64
+            }
+            impl ::core::convert::From<Open> for Y {
+                fn from(src: Open) -> Self {
+                    let id = 0;
+                    Self {
+                        id: id
+                    }
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+    }
+
+    #[test]
+    fn test_derive_struct_to_unit() {
+        let input = quote! {
+            #[mapstruct(
+                reverse,
+                struct Y {
+                    -id = 0,
+                    -name = String::new(),
+                }
+            )]
+            struct X {
+                id: u64,
+                name: String,
+            }
+        };
+        let expected = quote! {
+            struct Y;
+            impl ::core::convert::From<Y> for X {
+                fn from(src: Y) -> Self {
+                    let id = 0;
+                    let name = String::new();
+                    Self {
+                        id: id,
+                        name: name
+                    }
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+    }
+
+    #[test]
+    fn test_derive_struct_to_tuple() {
+        let input = quote! {
+            #[mapstruct(
+                from,
+                reverse,
+                struct Y(pub name, id, -age = 0);
+            )]
+            struct X {
+                id: u64,
+                name: String,
+                age: u8,
+            }
+        };
+        let expected = quote! {
+            struct Y(pub String, u64);
+            impl ::core::convert::From<X> for Y {
+                fn from(src: X) -> Self {
+                    Self(src.name, src.id)
+                }
+            }
+            impl ::core::convert::From<Y> for X {
+                fn from(src: Y) -> Self {
+                    let age = 0;
+                    Self {
+                        id: src.1,
+                        name: src.0,
+                        age: age
+                    }
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+
+        let input = quote! {
+            #[mapstruct(
+                struct Y(name);
+            )]
+            struct X {
+                id: u64,
+                name: String,
+            }
+        };
+        let output = derive(input).to_string();
+        assert!(output.contains("Field has no position, place it or remove it with `-`"), "{}", output);
+
+        let input = quote! {
+            #[mapstruct(
+                struct Y(id, +#[serde(skip)] u8);
+            )]
+            struct X {
+                id: u64,
+            }
+        };
+        let expected = quote! {
+            struct Y(u64, #[serde(skip)] u8);
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+    }
+
+    #[test]
+    fn test_derive_tuple_to_struct() {
+        let input = quote! {
+            #[mapstruct(
+                from,
+                struct Y {
+                    1 -> name: Option<String>,
+                    0 -> pub id,
+                    +active: bool = true,
+                }
+            )]
+            struct X(u64, String);
+        };
+        let expected = quote! {
+            struct Y {
+                name: Option<String>,
+                pub id: u64,
+                active: bool
+            }
+            impl ::core::convert::From<X> for Y {
+                fn from(src: X) -> Self {
+                    let active = true;
+                    Self {
+                        name: ::core::option::Option::Some(src.1),
+                        id: src.0,
+                        active: active
+                    }
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+
+        let input = quote! {
+            #[mapstruct(
+                struct Y {
+                    0 -> id,
+                }
+            )]
+            struct X(u64, String);
+        };
+        let output = derive(input).to_string();
+        assert!(output.contains("Field 1 has no name, name it with `1 -> name` or remove it with `-1`"), "{}", output);
+
+        let input = quote! {
+            #[mapstruct(
+                struct Y {
+                    0 -> id,
+                    +#[serde(default)] pub active: bool,
+                }
+            )]
+            struct X(u64);
+        };
+        let expected = quote! {
+            struct Y {
+                id: u64,
+                #[serde(default)]
+                pub active: bool
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+    }
+
     #[test]
     fn test_derive_enum_tuple() {
         let input = quote! {
@@ -309,4 +1988,227 @@ mod tests {
         };
         assert_eq!(expected.to_string(), derive(input).to_string());
     }
+
+    #[test]
+    fn test_derive_enum_tuple_indexed() {
+        let input = quote! {
+            #[mapstruct(
+                from,
+                enum Y {
+                    ~A(2: i128 with i128::from),
+                    -B(1),
+                    +C(0: u8 = 0),
+                    ~D(-_, ..),
+                }
+            )]
+            enum X {
+                A(i8, i16, i32),
+                B(u8, u16),
+                C(bool),
+                D(f32, f64, bool),
+            }
+        };
+        let expected = quote! {
+            enum Y {
+                A(i8, i16, i128),
+                B(u8),
+                C(u8, bool),
+                D(f64, bool)
+            }
+            impl ::core::convert::From<X> for Y {
+                fn from(src: X) -> Self {
+                    match src {
+                        X::A(field0, field1, field2) => {
+                            Self::A(field0, field1, i128::from(field2))
+                        },
+                        X::B(field0, _) => {
+                            Self::B(field0)
+                        },
+                        X::C(field0) => {
+                            let value0 = 0;
+                            Self::C(value0, field0)
+                        },
+                        X::D(_, field1, field2) => {
+                            Self::D(field1, field2)
+                        },
+                    }
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+
+        let input = quote! {
+            #[mapstruct(
+                enum Y {
+                    ~A(_, 0: i64),
+                }
+            )]
+            enum X {
+                A(i8, i16, i32),
+            }
+        };
+        let output = derive(input).to_string();
+        assert!(output.contains("Cannot change field twice"), "{}", output);
+
+        let input = quote! {
+            #[mapstruct(
+                enum Y {
+                    ~B(.., _),
+                }
+            )]
+            enum X {
+                B(u8, u16),
+            }
+        };
+        let output = derive(input).to_string();
+        assert!(output.contains("Expected `..` after every change by order"), "{}", output);
+    }
+
+    #[test]
+    fn test_derive_enum_positions() {
+        let input = quote! {
+            #[mapstruct(
+                enum Y {
+                    +after(A) E(i8),
+                    +before(A) Z,
+                }
+            )]
+            #[mapstruct(
+                order(C, A),
+                enum W {
+                    -B,
+                }
+            )]
+            enum X {
+                A,
+                B(u8),
+                C,
+            }
+        };
+        let expected = quote! {
+            enum Y {
+                Z,
+                A,
+                E(i8),
+                B(u8),
+                C
+            }
+            enum W {
+                C,
+                A
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+    }
+
+    #[test]
+    fn test_derive_enum_from() {
+        let input = quote! {
+            #[mapstruct(
+                from,
+                enum Y {
+                    ~A -> D,
+                    ~B(_, ~i64 with i64::from, -i16),
+                    ~C {
+                        ~id -> c_id,
+                        -name,
+                    },
+                    +E,
+                }
+            )]
+            enum X {
+                A,
+                B(i8, i32, i16),
+                C {
+                    id: i16,
+                    name: String,
+                },
+            }
+        };
+        let expected = quote! {
+            enum Y {
+                D,
+                B(i8, i64),
+                C {
+                    c_id: i16
+                },
+                E
+            }
+            impl ::core::convert::From<X> for Y {
+                fn from(src: X) -> Self {
+                    match src {
+                        X::A => { Self::D },
+                        X::B(field0, field1, _) => { Self::B(field0, i64::from(field1)) },
+                        #[allow(unused_variables)] X::C { id, name, } => {
+                            Self::C {
+                                c_id: id
+                            }
+                        },
+                    }
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+    }
+
+    #[test]
+    fn test_derive_enum_try_from_reverse() {
+        let input = quote! {
+            #[mapstruct(
+                try_from,
+                reverse,
+                enum Y {
+                    -A,
+                    ~B -> D,
+                }
+            )]
+            enum X {
+                A(i64),
+                B(i32),
+            }
+        };
+        let expected = quote! {
+            enum Y {
+                D(i32)
+            }
+            impl ::core::convert::TryFrom<X> for Y {
+                type Error = X;
+
+                fn try_from(src: X) -> ::core::result::Result<Self, Self::Error> {
+                    ::core::result::Result::Ok({
+                        match src {
+                            X::A { .. } => return ::core::result::Result::Err(src),
+                            X::B(field0) => { Self::D(field0) },
+                        }
+                    })
+                }
+            }
+            impl ::core::convert::From<Y> for X {
+                fn from(src: Y) -> Self {
+                    match src {
+                        Y::D(field0) => { Self::B(field0) },
+                    }
+                }
+            }
+        };
+        assert_eq!(expected.to_string(), derive(input).to_string());
+    }
+
+    #[test]
+    fn test_derive_enum_from_removed() {
+        let input = quote! {
+            #[mapstruct(
+                from,
+                enum Y {
+                    -A,
+                }
+            )]
+            enum X {
+                A,
+                B,
+            }
+        };
+        let output = derive(input).to_string();
+        assert!(output.contains("variant `A` is removed, use `try_from`"), "{}", output);
+    }
 }
@@ -2,36 +2,64 @@ use quote::ToTokens;
 use syn::FieldMutability;
 use syn::parse::Parse;
 
+use crate::attribute_change::AttributeChange;
+use crate::conversion::FieldValue;
+use crate::converter::Converter;
+use crate::order::Position;
+use crate::selector::FieldSelector;
+use crate::template;
 use crate::transformer::Transformer;
+use crate::unnamed_field_change::parse_value;
 
 /// Represents a change to a named field in a struct.
 pub enum NamedFieldChange {
     /// Add a new field to the struct.
     Add {
+        /// Where to insert the field, written as `before(id)` or `after(id)`. Appended if not given.
+        position: Option<Position>,
+        /// The attributes of the new field, written before the visibility.
+        attrs: Vec<syn::Attribute>,
         /// The visibility of the new field. This is the `pub` or `pub(crate)` part of the field.
         visibility: syn::Visibility,
         /// The identifier of the new field.
         ident: syn::Ident,
         /// The type of the new field.
         ty: syn::Type,
+        /// The expression computing the field in generated conversions, given after `=`.
+        /// It can read the source through `src`.
+        init: Option<syn::Expr>,
     },
     /// Remove a field from the struct.
     Remove {
-        /// The identifier of the field to remove.
-        ident: syn::Ident,
+        /// The field to remove, or a pattern like `audit_*` removing every matching field.
+        selector: FieldSelector,
         /// The type of the field to remove. This is optional.
         ty: Option<syn::Type>,
+        /// The value of the field in reverse conversions, given after `=`.
+        /// It can read the derived value through `src`.
+        default: Option<syn::Expr>,
     },
     /// Change a field.
     Change {
         /// The visibility of the new field. This is the `pub` or `pub(crate)` part of the field.
+        /// The field keeps the visibility of the source if none is written.
         visibility: syn::Visibility,
-        /// The identifier of the field to change.
-        ident: syn::Ident,
+        /// The field to change, or a pattern like `*_at` changing every matching field.
+        selector: FieldSelector,
         /// The new identifier of the field if it is being renamed.
         to: Option<syn::Ident>,
         /// The new type of the field if it is being retyped.
         ty: Option<syn::Type>,
+        /// The changes to the attributes of the field, e.g. `#[-serde] #[+serde(skip)]`.
+        attrs: Vec<AttributeChange>,
+        /// The conversion from the old value, given after `with`.
+        with: Option<Box<Converter>>,
+    },
+    /// Keep a field as is, indicated by a `=` prefix.
+    /// Once a field is picked, every field which is not mentioned is removed.
+    Pick {
+        /// The field to keep, or a pattern keeping every matching field.
+        selector: FieldSelector,
     },
 }
 
@@ -39,15 +67,21 @@ impl Parse for NamedFieldChange {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
         if input.peek(syn::Token![+]) {
             input.parse::<syn::Token![+]>()?;
+            let position = input.call(Position::parse_optional)?;
+            let attrs = input.call(syn::Attribute::parse_outer)?;
             let visibility = input.parse()?;
             let name = input.parse()?;
             input.parse::<syn::Token![:]>()?;
             let ty = input.parse()?;
+            let init = input.call(parse_value)?;
 
             return Ok(NamedFieldChange::Add {
+                position,
+                attrs,
                 visibility,
                 ident: name,
                 ty,
+                init,
             })
         }
 
@@ -55,77 +89,68 @@ impl Parse for NamedFieldChange {
             input.parse::<syn::Token![-]>()?;
             let name = input.parse()?;
 
-            if input.peek(syn::Token![:]) {
+            let ty = if input.peek(syn::Token![:]) {
                 input.parse::<syn::Token![:]>()?;
-                let ty = input.parse()?;
-                return Ok(NamedFieldChange::Remove {
-                    ident: name,
-                    ty: Some(ty),
-                })
-            }
+                Some(input.parse()?)
+            } else {
+                None
+            };
+
+            let default = input.call(parse_value)?;
 
             return Ok(NamedFieldChange::Remove {
-                ident: name,
-                ty: None,
+                selector: name,
+                ty,
+                default,
             })
         }
 
         if input.peek(syn::Token![~]) {
             input.parse::<syn::Token![~]>()?;
             let visibility = input.parse()?;
-            let from = input.parse()?;
-
-            if input.peek(syn::Token![:]) {
-                input.parse::<syn::Token![:]>()?;
-                let ty = input.parse()?;
-
-                return Ok(NamedFieldChange::Change {
-                    visibility,
-                    ident: from,
-                    to: None,
-                    ty: Some(ty),
-                })
-            }
+            let from: FieldSelector = input.parse()?;
 
-            if input.peek(syn::Token![->]) {
+            let (visibility, to) = if input.peek(syn::Token![->]) {
                 input.parse::<syn::Token![->]>()?;
 
+                if from.is_pattern() {
+                    return Err(input.error("cannot rename the fields matched by a pattern"))
+                }
+
                 if !matches!(visibility, syn::Visibility::Inherited) {
                     return Err(input.error("expected visibility to be provided after `->` and not after `~`"))
                 }
 
-                let visibility = input.parse()?;
-                let to = input.parse()?;
-
-                if input.peek(syn::Token![:]) {
-                    input.parse::<syn::Token![:]>()?;
-                    let ty = input.parse()?;
-
-                    return Ok(NamedFieldChange::Change {
-                        visibility,
-                        ident: from,
-                        to: Some(to),
-                        ty: Some(ty),
-                    })
-                }
+                (input.parse()?, Some(input.parse()?))
+            } else {
+                (visibility, None)
+            };
 
-                return Ok(NamedFieldChange::Change {
-                    visibility,
-                    ident: from,
-                    to: Some(to),
-                    ty: None,
-                })
-            }
+            let ty = if input.peek(syn::Token![:]) {
+                input.parse::<syn::Token![:]>()?;
+                Some(input.parse()?)
+            } else {
+                None
+            };
 
             return Ok(NamedFieldChange::Change {
                 visibility,
-                ident: from,
-                to: None,
-                ty: None,
+                selector: from,
+                to,
+                ty,
+                attrs: input.call(AttributeChange::parse_all)?,
+                with: input.call(Converter::parse_optional)?,
+            })
+        }
+
+        if input.peek(syn::Token![=]) {
+            input.parse::<syn::Token![=]>()?;
+            return Ok(NamedFieldChange::Pick {
+                selector: input.parse()?,
             })
         }
 
-        Err(input.error("expected one of +, -, ~"))
+        Err(input.error("expected one of +, -, ~, ="))
     }
 }
 
@@ -135,9 +160,9 @@ impl Transformer for NamedFieldChange {
 
     fn create(&self) -> syn::Result<Self::CreateIter> {
         match self {
-            NamedFieldChange::Add { visibility, ident, ty } => {
+            NamedFieldChange::Add { attrs, visibility, ident, ty, .. } => {
                 let field = syn::Field {
-                    attrs: Vec::new(),
+                    attrs: attrs.clone(),
                     vis: visibility.clone(),
                     mutability: FieldMutability::None,
                     ident: Some(ident.clone()),
@@ -153,8 +178,8 @@ impl Transformer for NamedFieldChange {
 
     fn remove(&self, field: &Self::Item) -> syn::Result<bool> {
         match self {
-            NamedFieldChange::Remove { ident, ty } => {
-                if field.ident.as_ref().unwrap() == ident {
+            NamedFieldChange::Remove { selector, ty, .. } => {
+                if selector.matches(field.ident.as_ref().unwrap()) {
                     if let Some(ty) = ty {
                         if field.ty.to_token_stream().to_string() == ty.to_token_stream().to_string() {
                             return Ok(true)
@@ -172,18 +197,23 @@ impl Transformer for NamedFieldChange {
 
     fn transform(&self, field: &mut Self::Item) -> syn::Result<bool> {
         match self {
-            NamedFieldChange::Change { visibility, ident, to, ty } if field.ident.as_ref().unwrap() == ident => {
+            NamedFieldChange::Change { visibility, selector, to, ty, attrs, .. } if selector.matches(field.ident.as_ref().unwrap()) => {
+                AttributeChange::apply(attrs, &mut field.attrs)?;
+
                 if let Some(to) = to {
                     field.ident = Some(to.clone());
                 }
 
                 if let Some(ty) = ty {
-                    field.ty = ty.clone();
+                    field.ty = template::expand(ty, &field.ty);
                 }
 
-                field.vis = visibility.clone();
+                if !matches!(visibility, syn::Visibility::Inherited) {
+                    field.vis = visibility.clone();
+                }
                 Ok(true)
             },
+            NamedFieldChange::Pick { selector } => Ok(selector.matches(field.ident.as_ref().unwrap())),
             _ => Ok(false),
         }
     }
@@ -191,15 +221,26 @@ impl Transformer for NamedFieldChange {
 
 
 impl NamedFieldChange {
+    /// Describes how a field touched by this change is produced in a conversion.
+    pub fn value(&self) -> FieldValue {
+        match self {
+            NamedFieldChange::Add { init, .. } => FieldValue::Added { span: self.span(), init: init.clone() },
+            NamedFieldChange::Remove { default, .. } => FieldValue::Removed { span: self.span(), default: default.clone() },
+            NamedFieldChange::Change { ty: None, with: None, .. } => FieldValue::Moved,
+            NamedFieldChange::Pick { .. } => FieldValue::Moved,
+            NamedFieldChange::Change { with, .. } => FieldValue::Retyped { span: self.span(), with: with.clone() },
+        }
+    }
+
     pub fn span(&self) -> proc_macro2::Span {
         use syn::spanned::Spanned;
 
         match self {
-            NamedFieldChange::Add { visibility, ident: _, ty } => {
+            NamedFieldChange::Add { visibility, ty, .. } => {
                 visibility.span().join(ty.span()).unwrap_or_else(|| visibility.span())
             },
-            NamedFieldChange::Remove { ident, ty } => {
-                let mut span = ident.span();
+            NamedFieldChange::Remove { selector, ty, .. } => {
+                let mut span = selector.span();
 
                 if let Some(ty) = ty {
                     span = span.join(ty.span()).unwrap_or_else(|| span);
@@ -207,8 +248,8 @@ impl NamedFieldChange {
 
                 span
             },
-            NamedFieldChange::Change { visibility, ident, to, ty } => {
-                let mut span = visibility.span().join(ident.span()).unwrap_or_else(|| visibility.span());
+            NamedFieldChange::Change { visibility, selector, to, ty, .. } => {
+                let mut span = visibility.span().join(selector.span()).unwrap_or_else(|| visibility.span());
 
                 if let Some(to) = to {
                     span = span.join(to.span()).unwrap_or_else(|| span);
@@ -220,6 +261,35 @@ impl NamedFieldChange {
 
                 span
             },
+            NamedFieldChange::Pick { selector } => selector.span(),
+        }
+    }
+
+    /// Where an added field is inserted.
+    pub fn position(&self) -> Option<&Position> {
+        match self {
+            NamedFieldChange::Add { position, .. } => position.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// The fields the change applies to, if it applies to existing fields.
+    pub fn selector(&self) -> Option<&FieldSelector> {
+        match self {
+            NamedFieldChange::Add { .. } => None,
+            NamedFieldChange::Remove { selector, .. }
+            | NamedFieldChange::Change { selector, .. }
+            | NamedFieldChange::Pick { selector } => Some(selector),
         }
     }
+
+    /// Whether the change selects fields by a pattern, which only applies to fields no other change mentions.
+    pub fn is_pattern(&self) -> bool {
+        self.selector().is_some_and(FieldSelector::is_pattern)
+    }
+
+    /// Whether the change picks a field, which removes every field that is not mentioned.
+    pub fn is_pick(&self) -> bool {
+        matches!(self, NamedFieldChange::Pick { .. })
+    }
 }
\ No newline at end of file
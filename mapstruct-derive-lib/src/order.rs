@@ -0,0 +1,155 @@
+use syn::parse::{Parse, ParseStream};
+
+use crate::conversion::{FieldMapping, VariantMapping};
+
+mod kw {
+    syn::custom_keyword!(before);
+    syn::custom_keyword!(after);
+}
+
+/// Where an added field, variant or generic parameter is inserted, written as `before(id)` or `after(id)`.
+/// Lifetimes are given with their `'`, e.g. `before('a)`. Generic parameters also take it after them, e.g. `'a before T`.
+pub enum Position {
+    Before(syn::Ident),
+    After(syn::Ident),
+}
+
+impl Parse for Position {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let content;
+        if input.peek(kw::before) {
+            input.parse::<kw::before>()?;
+            syn::parenthesized!(content in input);
+            return Ok(Position::Before(content.call(parse_anchor)?));
+        }
+
+        input.parse::<kw::after>()?;
+        syn::parenthesized!(content in input);
+        Ok(Position::After(content.call(parse_anchor)?))
+    }
+}
+
+/// The name of a position, lifetimes without their `'` like in [`crate::generic::param_ident`].
+fn parse_anchor(input: ParseStream) -> syn::Result<syn::Ident> {
+    if input.peek(syn::Lifetime) {
+        return Ok(input.parse::<syn::Lifetime>()?.ident);
+    }
+
+    input.parse()
+}
+
+impl Position {
+    /// Parses a leading `before(...)` or `after(...)` if present.
+    pub fn parse_optional(input: ParseStream) -> syn::Result<Option<Self>> {
+        if (input.peek(kw::before) || input.peek(kw::after)) && input.peek2(syn::token::Paren) {
+            return Ok(Some(input.parse()?));
+        }
+
+        Ok(None)
+    }
+
+    /// Parses a trailing `before T` or `after 'a` of an added generic parameter if present.
+    pub fn parse_trailing(input: ParseStream) -> syn::Result<Option<Self>> {
+        let anchor = input.peek2(syn::Ident) || input.peek2(syn::Lifetime);
+        if input.peek(kw::before) && anchor {
+            input.parse::<kw::before>()?;
+            return Ok(Some(Position::Before(input.call(parse_anchor)?)));
+        }
+        if input.peek(kw::after) && anchor {
+            input.parse::<kw::after>()?;
+            return Ok(Some(Position::After(input.call(parse_anchor)?)));
+        }
+
+        Ok(None)
+    }
+
+    /// The index to insert at, given the names of the current fields or variants.
+    pub fn index<'a>(&self, names: impl Iterator<Item = Option<&'a syn::Ident>>) -> syn::Result<usize> {
+        let (Position::Before(anchor) | Position::After(anchor)) = self;
+        let index = names.into_iter()
+            .position(|name| name == Some(anchor))
+            .ok_or_else(|| syn::Error::new_spanned(anchor, "No field, variant or generic parameter with this name"))?;
+
+        match self {
+            Position::Before(_) => Ok(index),
+            Position::After(_) => Ok(index + 1),
+        }
+    }
+}
+
+/// Reorders the fields of the result as listed in `order`, either by name or by position.
+/// Every field of the result has to be listed exactly once.
+pub fn fields(order: &[syn::Member], mappings: &mut Vec<FieldMapping>) -> syn::Result<syn::Fields> {
+    let targets = mappings.iter()
+        .filter_map(|mapping| mapping.target.as_ref())
+        .enumerate()
+        .map(|(index, target)| match &target.ident {
+            Some(ident) => syn::Member::Named(ident.clone()),
+            None => syn::Member::Unnamed(index.into()),
+        })
+        .collect::<Vec<_>>();
+    let positions = positions(order, &targets)?;
+
+    let (kept, removed): (Vec<_>, Vec<_>) = std::mem::take(mappings).into_iter()
+        .partition(|mapping| mapping.target.is_some());
+    let mut kept = kept.into_iter().map(Some).collect::<Vec<_>>();
+    mappings.extend(positions.into_iter().map(|position| kept[position].take().unwrap()));
+    mappings.extend(removed);
+
+    let fields = mappings.iter().filter_map(|mapping| mapping.target.clone());
+    Ok(match targets.first() {
+        Some(syn::Member::Unnamed(_)) => syn::Fields::Unnamed(syn::FieldsUnnamed {
+            paren_token: Default::default(),
+            unnamed: fields.collect(),
+        }),
+        _ => syn::Fields::Named(syn::FieldsNamed {
+            brace_token: Default::default(),
+            named: fields.collect(),
+        }),
+    })
+}
+
+/// Reorders the variants of the result as listed in `order`.
+/// Every variant of the result has to be listed exactly once.
+pub fn variants(order: &[syn::Member], mappings: &mut Vec<VariantMapping>) -> syn::Result<Vec<syn::Variant>> {
+    let targets = mappings.iter()
+        .filter_map(|mapping| mapping.target.as_ref())
+        .map(|target| syn::Member::Named(target.ident.clone()))
+        .collect::<Vec<_>>();
+    let positions = positions(order, &targets)?;
+
+    let (kept, removed): (Vec<_>, Vec<_>) = std::mem::take(mappings).into_iter()
+        .partition(|mapping| mapping.target.is_some());
+    let mut kept = kept.into_iter().map(Some).collect::<Vec<_>>();
+    mappings.extend(positions.into_iter().map(|position| kept[position].take().unwrap()));
+    mappings.extend(removed);
+
+    Ok(mappings.iter().filter_map(|mapping| mapping.target.clone()).collect())
+}
+
+/// Finds the position of every member of `order` in `targets`, which must all be listed exactly once.
+fn positions(order: &[syn::Member], targets: &[syn::Member]) -> syn::Result<Vec<usize>> {
+    let mut positions = Vec::new();
+    for member in order {
+        let position = targets.iter()
+            .position(|target| target == member)
+            .ok_or_else(|| syn::Error::new_spanned(member, "No field or variant with this name"))?;
+        if positions.contains(&position) {
+            return Err(syn::Error::new_spanned(member, "Listed twice"));
+        }
+        positions.push(position);
+    }
+
+    if let Some(missing) = targets.iter().enumerate().find(|(position, _)| !positions.contains(position)) {
+        let name = match missing.1 {
+            syn::Member::Named(ident) => ident.to_string(),
+            syn::Member::Unnamed(index) => index.index.to_string(),
+        };
+        return Err(syn::Error::new_spanned(
+            order.last(),
+            format!("`order` has to list every field or variant, `{}` is missing", name),
+        ));
+    }
+
+    Ok(positions)
+}
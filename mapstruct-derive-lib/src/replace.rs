@@ -0,0 +1,90 @@
+use quote::ToTokens;
+use syn::spanned::Spanned;
+use syn::visit_mut::VisitMut;
+
+use crate::conversion::{FieldMapping, FieldValue};
+use crate::converter::Converter;
+use crate::directive::Directive;
+
+/// The `replace` directives of a derived type, swapping a type wholesale in every field,
+/// e.g. `replace String => Arc<str>`.
+pub struct Replacements<'a> {
+    replacements: Vec<(&'a syn::Type, &'a syn::Type, &'a Option<Box<Converter>>)>,
+    /// Whether each replacement replaced something.
+    used: Vec<bool>,
+}
+
+impl<'a> Replacements<'a> {
+    pub fn new(directives: &'a [Directive]) -> Self {
+        let replacements = directives.iter()
+            .filter_map(|directive| match directive {
+                Directive::Replace(_, from, to, with) => Some((&**from, &**to, with)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        let used = vec![false; replacements.len()];
+
+        Replacements { replacements, used }
+    }
+
+    /// Replaces the types in `field`, including nested ones like `Vec<String>`, and keeps its `mapping` in sync.
+    /// Only fields moved over from the source are replaced, retyped and added fields keep the type written for them.
+    /// A replaced field becomes retyped, converted by the `with` of the replacement if the whole type was replaced.
+    pub fn field(&mut self, field: &mut syn::Field, mapping: &mut FieldMapping) {
+        if !matches!(mapping.value, FieldValue::Moved) {
+            return;
+        }
+
+        let mut with = None;
+        let mut replaced = false;
+        for ((from, to, converter), used) in self.replacements.iter().zip(self.used.iter_mut()) {
+            if same(&field.ty, from) {
+                field.ty = (*to).clone();
+                with = converter.as_ref();
+            } else {
+                let mut replace = Replace { from, to, replaced: false };
+                replace.visit_type_mut(&mut field.ty);
+                if !replace.replaced {
+                    continue;
+                }
+            }
+
+            *used = true;
+            replaced = true;
+        }
+
+        if replaced {
+            mapping.value = FieldValue::Retyped { span: field.ty.span(), with: with.cloned() };
+            mapping.target = Some(field.clone());
+        }
+    }
+
+    /// Fails for the first replacement which did not replace anything.
+    pub fn finish(self) -> syn::Result<()> {
+        match self.replacements.iter().zip(self.used).find(|(_, used)| !used) {
+            Some(((from, _, _), _)) => Err(syn::Error::new_spanned(from, "Nothing was replaced, no field uses this type")),
+            None => Ok(()),
+        }
+    }
+}
+
+struct Replace<'a> {
+    from: &'a syn::Type,
+    to: &'a syn::Type,
+    replaced: bool,
+}
+
+impl VisitMut for Replace<'_> {
+    fn visit_type_mut(&mut self, ty: &mut syn::Type) {
+        if same(ty, self.from) {
+            *ty = self.to.clone();
+            self.replaced = true;
+        } else {
+            syn::visit_mut::visit_type_mut(self, ty);
+        }
+    }
+}
+
+fn same(a: &syn::Type, b: &syn::Type) -> bool {
+    a.to_token_stream().to_string() == b.to_token_stream().to_string()
+}
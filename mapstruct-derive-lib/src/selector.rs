@@ -0,0 +1,98 @@
+use proc_macro2::Span;
+use syn::parse::{Parse, ParseStream};
+
+/// Selects the fields a change applies to, either by name, by a set of names like `{name, email}`
+/// or by a pattern like `audit_*`, `*_at` or `*`.
+pub enum FieldSelector {
+    /// A single field.
+    Ident(syn::Ident),
+    /// Every listed field, each of them must exist.
+    Set {
+        brace: syn::token::Brace,
+        idents: Vec<syn::Ident>,
+    },
+    /// Every field of the source starting with the prefix and ending with the suffix.
+    Pattern {
+        prefix: Option<syn::Ident>,
+        star: syn::Token![*],
+        suffix: Option<syn::Ident>,
+    },
+}
+
+impl Parse for FieldSelector {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(syn::token::Brace) {
+            let content;
+            let brace = syn::braced!(content in input);
+            let idents = content.parse_terminated(syn::Ident::parse, syn::Token![,])?;
+            return Ok(FieldSelector::Set { brace, idents: idents.into_iter().collect() });
+        }
+
+        if input.peek(syn::Ident) && !input.peek2(syn::Token![*]) {
+            return Ok(FieldSelector::Ident(input.parse()?));
+        }
+
+        let prefix = if input.peek(syn::Ident) {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+        let star = input.parse()?;
+        let suffix = if input.peek(syn::Ident) {
+            Some(input.parse()?)
+        } else {
+            None
+        };
+
+        Ok(FieldSelector::Pattern { prefix, star, suffix })
+    }
+}
+
+impl FieldSelector {
+    /// Whether the field `ident` is selected.
+    pub fn matches(&self, ident: &syn::Ident) -> bool {
+        match self {
+            FieldSelector::Ident(selected) => ident == selected,
+            FieldSelector::Set { idents, .. } => idents.contains(ident),
+            FieldSelector::Pattern { prefix, suffix, .. } => {
+                let name = ident.to_string();
+                let name = name.strip_prefix("r#").unwrap_or(&name);
+                let prefix = prefix.as_ref().map(ToString::to_string).unwrap_or_default();
+                let suffix = suffix.as_ref().map(ToString::to_string).unwrap_or_default();
+
+                name.len() >= prefix.len() + suffix.len()
+                    && name.starts_with(&prefix)
+                    && name.ends_with(&suffix)
+            },
+        }
+    }
+
+    /// The first name of a set that is not one of `fields`.
+    pub fn missing<'a>(&'a self, fields: &[&syn::Ident]) -> Option<&'a syn::Ident> {
+        match self {
+            FieldSelector::Set { idents, .. } => idents.iter().find(|ident| !fields.contains(ident)),
+            _ => None,
+        }
+    }
+
+    /// Whether the selector matches by a pattern instead of by names.
+    pub fn is_pattern(&self) -> bool {
+        matches!(self, FieldSelector::Pattern { .. })
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            FieldSelector::Ident(ident) => ident.span(),
+            FieldSelector::Set { brace, .. } => brace.span.join(),
+            FieldSelector::Pattern { prefix, star, suffix } => {
+                let mut span = prefix.as_ref().map_or(star.span, |prefix| prefix.span());
+                span = span.join(star.span).unwrap_or(span);
+                if let Some(suffix) = suffix {
+                    span = span.join(suffix.span()).unwrap_or(span);
+                }
+
+                span
+            },
+        }
+    }
+}
@@ -0,0 +1,310 @@
+use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
+
+use crate::conversion::{FieldMapping, FieldValue};
+use crate::converter::Converter;
+use crate::template;
+use crate::unnamed_field_change::parse_value;
+
+/// Turns a struct with named fields into a tuple struct, written as `(id, name: &'a str, +u8)`.
+pub struct ToTupleChange {
+    changes: Vec<ToTupleFieldChange>,
+}
+
+/// A position of a tuple struct created from named fields.
+pub enum ToTupleFieldChange {
+    /// Place a named field at the next position, optionally retyped, e.g. `pub name: &'a str with f`.
+    Place {
+        visibility: syn::Visibility,
+        ident: syn::Ident,
+        ty: Option<syn::Type>,
+        with: Option<Box<Converter>>,
+    },
+    /// Add a new position, indicated by a `+` prefix.
+    Add {
+        attrs: Vec<syn::Attribute>,
+        visibility: syn::Visibility,
+        ty: syn::Type,
+        init: Option<syn::Expr>,
+    },
+    /// Drop a named field, indicated by a `-` prefix.
+    Remove {
+        ident: syn::Ident,
+        default: Option<syn::Expr>,
+    },
+}
+
+/// Turns a tuple struct into a struct with named fields, written as `{ 0 -> id, 1 -> name: &'a str }`.
+pub struct ToNamedChange {
+    changes: Vec<ToNamedFieldChange>,
+}
+
+/// A field of a struct created from tuple fields.
+pub enum ToNamedFieldChange {
+    /// Name a position, optionally retyped, e.g. `1 -> pub name: &'a str with f`.
+    Name {
+        index: syn::Index,
+        visibility: syn::Visibility,
+        ident: syn::Ident,
+        ty: Option<syn::Type>,
+        with: Option<Box<Converter>>,
+    },
+    /// Add a new field, indicated by a `+` prefix.
+    Add {
+        attrs: Vec<syn::Attribute>,
+        visibility: syn::Visibility,
+        ident: syn::Ident,
+        ty: syn::Type,
+        init: Option<syn::Expr>,
+    },
+    /// Drop a position, indicated by a `-` prefix.
+    Remove {
+        index: syn::Index,
+        default: Option<syn::Expr>,
+    },
+}
+
+impl Parse for ToTupleChange {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let content;
+        syn::parenthesized!(content in input);
+        let changes = content.parse_terminated(ToTupleFieldChange::parse, syn::Token![,])?;
+        let changes = changes.into_iter().collect();
+        Ok(ToTupleChange { changes })
+    }
+}
+
+impl Parse for ToTupleFieldChange {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(syn::Token![+]) {
+            input.parse::<syn::Token![+]>()?;
+            return Ok(ToTupleFieldChange::Add {
+                attrs: input.call(syn::Attribute::parse_outer)?,
+                visibility: input.parse()?,
+                ty: input.parse()?,
+                init: input.call(parse_value)?,
+            });
+        }
+
+        if input.peek(syn::Token![-]) {
+            input.parse::<syn::Token![-]>()?;
+            return Ok(ToTupleFieldChange::Remove {
+                ident: input.parse()?,
+                default: input.call(parse_value)?,
+            });
+        }
+
+        let visibility = input.parse()?;
+        let ident = input.parse()?;
+        let ty = if input.peek(syn::Token![:]) {
+            input.parse::<syn::Token![:]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+
+        Ok(ToTupleFieldChange::Place {
+            visibility,
+            ident,
+            ty,
+            with: input.call(Converter::parse_optional)?,
+        })
+    }
+}
+
+impl Parse for ToNamedChange {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let content;
+        syn::braced!(content in input);
+        let changes = content.parse_terminated(ToNamedFieldChange::parse, syn::Token![,])?;
+        let changes = changes.into_iter().collect();
+        Ok(ToNamedChange { changes })
+    }
+}
+
+impl Parse for ToNamedFieldChange {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(syn::Token![+]) {
+            input.parse::<syn::Token![+]>()?;
+            let attrs = input.call(syn::Attribute::parse_outer)?;
+            let visibility = input.parse()?;
+            let ident = input.parse()?;
+            input.parse::<syn::Token![:]>()?;
+            return Ok(ToNamedFieldChange::Add {
+                attrs,
+                visibility,
+                ident,
+                ty: input.parse()?,
+                init: input.call(parse_value)?,
+            });
+        }
+
+        if input.peek(syn::Token![-]) {
+            input.parse::<syn::Token![-]>()?;
+            return Ok(ToNamedFieldChange::Remove {
+                index: input.parse()?,
+                default: input.call(parse_value)?,
+            });
+        }
+
+        let index = input.parse()?;
+        input.parse::<syn::Token![->]>()?;
+        let visibility = input.parse()?;
+        let ident = input.parse()?;
+        let ty = if input.peek(syn::Token![:]) {
+            input.parse::<syn::Token![:]>()?;
+            Some(input.parse()?)
+        } else {
+            None
+        };
+
+        Ok(ToNamedFieldChange::Name {
+            index,
+            visibility,
+            ident,
+            ty,
+            with: input.call(Converter::parse_optional)?,
+        })
+    }
+}
+
+impl ToTupleChange {
+    /// Builds the tuple fields from `item` and returns how they relate to the named fields.
+    pub fn apply(&self, item: &syn::FieldsNamed) -> syn::Result<(syn::FieldsUnnamed, Vec<FieldMapping>)> {
+        let mut used = vec![false; item.named.len()];
+        let mut find = |ident: &syn::Ident| {
+            let (index, field) = item.named.iter()
+                .enumerate()
+                .find(|(_, field)| field.ident.as_ref() == Some(ident))
+                .ok_or_else(|| syn::Error::new_spanned(ident, "No field with this name"))?;
+            if std::mem::replace(&mut used[index], true) {
+                return Err(syn::Error::new_spanned(ident, "Cannot change field twice"));
+            }
+
+            Ok((index, field.clone()))
+        };
+
+        let mut mappings = Vec::new();
+        for change in &self.changes {
+            mappings.push(match change {
+                ToTupleFieldChange::Place { visibility, ident, ty, with } => {
+                    let (index, field) = find(ident)?;
+                    let target = syn::Field {
+                        vis: visibility.clone(),
+                        ident: None,
+                        colon_token: None,
+                        ty: ty.as_ref().map_or_else(|| field.ty.clone(), |ty| template::expand(ty, &field.ty)),
+                        ..field.clone()
+                    };
+                    let value = if ty.is_some() || with.is_some() {
+                        FieldValue::Retyped { span: ident.span(), with: with.clone() }
+                    } else {
+                        FieldValue::Moved
+                    };
+
+                    FieldMapping { source: Some((index, field)), target: Some(target), value }
+                },
+                ToTupleFieldChange::Add { attrs, visibility, ty, init } => FieldMapping {
+                    source: None,
+                    target: Some(new_field(attrs, visibility, None, ty)),
+                    value: FieldValue::Added { span: ty.span(), init: init.clone() },
+                },
+                ToTupleFieldChange::Remove { ident, default } => FieldMapping {
+                    source: Some(find(ident)?),
+                    target: None,
+                    value: FieldValue::Removed { span: ident.span(), default: default.clone() },
+                },
+            });
+        }
+
+        if let Some(index) = used.iter().position(|used| !used) {
+            return Err(syn::Error::new_spanned(
+                &item.named[index],
+                "Field has no position, place it or remove it with `-`",
+            ));
+        }
+
+        let fields = syn::FieldsUnnamed {
+            paren_token: Default::default(),
+            unnamed: mappings.iter().filter_map(|mapping| mapping.target.clone()).collect(),
+        };
+
+        Ok((fields, mappings))
+    }
+}
+
+impl ToNamedChange {
+    /// Builds the named fields from `item` and returns how they relate to the tuple fields.
+    pub fn apply(&self, item: &syn::FieldsUnnamed) -> syn::Result<(syn::FieldsNamed, Vec<FieldMapping>)> {
+        let mut used = vec![false; item.unnamed.len()];
+        let mut find = |index: &syn::Index| {
+            let position = index.index as usize;
+            let field = item.unnamed.iter()
+                .nth(position)
+                .ok_or_else(|| syn::Error::new_spanned(index, "No field at this position"))?;
+            if std::mem::replace(&mut used[position], true) {
+                return Err(syn::Error::new_spanned(index, "Cannot change field twice"));
+            }
+
+            Ok((position, field.clone()))
+        };
+
+        let mut mappings = Vec::new();
+        for change in &self.changes {
+            mappings.push(match change {
+                ToNamedFieldChange::Name { index, visibility, ident, ty, with } => {
+                    let (position, field) = find(index)?;
+                    let target = syn::Field {
+                        vis: visibility.clone(),
+                        ident: Some(ident.clone()),
+                        colon_token: Some(Default::default()),
+                        ty: ty.as_ref().map_or_else(|| field.ty.clone(), |ty| template::expand(ty, &field.ty)),
+                        ..field.clone()
+                    };
+                    let value = if ty.is_some() || with.is_some() {
+                        FieldValue::Retyped { span: ident.span(), with: with.clone() }
+                    } else {
+                        FieldValue::Moved
+                    };
+
+                    FieldMapping { source: Some((position, field)), target: Some(target), value }
+                },
+                ToNamedFieldChange::Add { attrs, visibility, ident, ty, init } => FieldMapping {
+                    source: None,
+                    target: Some(new_field(attrs, visibility, Some(ident), ty)),
+                    value: FieldValue::Added { span: ident.span(), init: init.clone() },
+                },
+                ToNamedFieldChange::Remove { index, default } => FieldMapping {
+                    source: Some(find(index)?),
+                    target: None,
+                    value: FieldValue::Removed { span: index.span, default: default.clone() },
+                },
+            });
+        }
+
+        if let Some(position) = used.iter().position(|used| !used) {
+            return Err(syn::Error::new_spanned(
+                &item.unnamed[position],
+                format!("Field {} has no name, name it with `{} -> name` or remove it with `-{}`", position, position, position),
+            ));
+        }
+
+        let fields = syn::FieldsNamed {
+            brace_token: Default::default(),
+            named: mappings.iter().filter_map(|mapping| mapping.target.clone()).collect(),
+        };
+
+        Ok((fields, mappings))
+    }
+}
+
+fn new_field(attrs: &[syn::Attribute], visibility: &syn::Visibility, ident: Option<&syn::Ident>, ty: &syn::Type) -> syn::Field {
+    syn::Field {
+        attrs: attrs.to_vec(),
+        vis: visibility.clone(),
+        mutability: syn::FieldMutability::None,
+        ident: ident.cloned(),
+        colon_token: ident.map(|_| Default::default()),
+        ty: ty.clone(),
+    }
+}
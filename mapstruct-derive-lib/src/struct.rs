@@ -1,21 +1,64 @@
-use syn::{Data, DeriveInput, GenericParam};
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{Data, DeriveInput};
 use syn::parse::Parse;
 
-use crate::generic::GenericChange;
+use crate::attribute_change::AttributeChange;
+use crate::conversion::{self, FieldMapping, FieldValue, Options};
+use crate::directive::Directive;
+use crate::generic::{self, GenericChange};
+use crate::order;
+use crate::replace::Replacements;
+use crate::shape_change::{ToNamedChange, ToTupleChange};
 use crate::struct_change::StructChange;
-use crate::transformer::Transformer;
+use crate::tuple_change::TupleChange;
 
 pub struct MapStruct {
-    attrs: Vec<syn::Attribute>,
+    directives: Vec<Directive>,
+    attrs: Vec<AttributeChange>,
     vis: syn::Visibility,
     ident: syn::Ident,
     generics: Vec<GenericChange>,
-    changes: StructChange,
+    /// The `{ ... }` or `( ... )` group, parsed once the shape of the source is known.
+    changes: Option<proc_macro2::TokenTree>,
+}
+
+/// The changes to the fields of a struct.
+enum FieldsChange {
+    /// Changes to named fields, written as `{ ... }`.
+    Named(StructChange),
+    /// Changes to tuple fields, written as `( ... );`.
+    Unnamed(TupleChange),
+    /// Named fields turned into a tuple struct, written as `( ... );`.
+    ToUnnamed(ToTupleChange),
+    /// Tuple fields turned into named fields, written as `{ ... }`.
+    ToNamed(ToNamedChange),
+    /// A unit struct, written as `;`.
+    Unit,
+}
+
+impl FieldsChange {
+    /// Parses `changes` against the shape of the `source` fields.
+    fn parse(source: &syn::Fields, changes: Option<proc_macro2::TokenTree>) -> syn::Result<Self> {
+        let Some(changes) = changes else {
+            return Ok(FieldsChange::Unit);
+        };
+        let braced = matches!(&changes, proc_macro2::TokenTree::Group(group) if group.delimiter() == proc_macro2::Delimiter::Brace);
+        let changes = changes.into_token_stream();
+
+        Ok(match (source, braced) {
+            (syn::Fields::Named(_), false) => FieldsChange::ToUnnamed(syn::parse2(changes)?),
+            (syn::Fields::Unnamed(_), true) => FieldsChange::ToNamed(syn::parse2(changes)?),
+            (_, true) => FieldsChange::Named(syn::parse2(changes)?),
+            (_, false) => FieldsChange::Unnamed(syn::parse2(changes)?),
+        })
+    }
 }
 
 impl Parse for MapStruct {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        let attrs = input.call(syn::Attribute::parse_outer)?;
+        let directives = input.call(Directive::parse_all)?;
+        let attrs = input.call(AttributeChange::parse_container)?;
         let vis = input.parse::<syn::Visibility>()?;
         input.parse::<syn::Token![struct]>()?;
         let ident = input.parse()?;
@@ -36,9 +79,19 @@ impl Parse for MapStruct {
             Vec::new()
         };
 
-        let changes = input.parse()?;
+        let changes = if input.peek(syn::token::Paren) || input.peek(syn::token::Brace) {
+            Some(input.parse()?)
+        } else if input.peek(syn::Token![;]) {
+            None
+        } else {
+            return Err(input.error("expected `{ ... }`, `( ... );` or `;`"));
+        };
+        if input.peek(syn::Token![;]) {
+            input.parse::<syn::Token![;]>()?;
+        }
 
         Ok(MapStruct {
+            directives,
             attrs,
             vis,
             ident,
@@ -49,47 +102,166 @@ impl Parse for MapStruct {
 }
 
 impl MapStruct {
-    pub(crate) fn transform(self, input: DeriveInput) -> syn::Result<DeriveInput> {
-        let mut input = input;
-        input.attrs = self.attrs;
+    pub(crate) fn transform(self, source: DeriveInput) -> syn::Result<TokenStream> {
+        let mut input = source.clone();
+        input.attrs = if self.directives.iter().any(|directive| matches!(directive, Directive::Inherit(_))) {
+            AttributeChange::inherited(&source.attrs)
+        } else {
+            Vec::new()
+        };
+        AttributeChange::apply(&self.attrs, &mut input.attrs)?;
         input.vis = self.vis;
         input.ident = self.ident;
 
-        let generic_removes = self.generics.iter()
-            .filter(|change| matches!(change, GenericChange::Remove(_)))
-            .map(|change| crate::unwrap_one_variant!(change, GenericChange::Remove(lifetime), lifetime))
-            .collect::<Vec<_>>();
-        input.generics.params = input.generics.params.into_iter()
-            .filter(|param| match param {
-                GenericParam::Lifetime(param) => {
-                    !generic_removes.contains(&&param.lifetime.ident)
-                },
-                GenericParam::Type(param) => {
-                    !generic_removes.contains(&&param.ident)
-                },
-                GenericParam::Const(param) => {
-                    !generic_removes.contains(&&param.ident)
-                },
-            })
-            .collect();
-        input.generics.params.extend(self.generics.into_iter()
-            .filter(|change| matches!(change, GenericChange::Add(_)))
-            .map(|change| crate::unwrap_one_variant!(change, GenericChange::Add(param), param))
-        );
-
-        match &mut input.data {
+        let removed = GenericChange::removed(&self.generics);
+        let substitutions = GenericChange::apply(self.generics, &mut input.generics)?;
+
+        let mut mappings = match &mut input.data {
             Data::Struct(data) => {
-                let fields_named = match &mut data.fields {
-                    syn::Fields::Named(fields_named) => fields_named,
-                    syn::Fields::Unnamed(_) => return Err(syn::Error::new_spanned(input, "tuple fields not supported"))?,
-                    syn::Fields::Unit => return Err(syn::Error::new_spanned(input, "unit fields not supported"))?,
+                let changes = FieldsChange::parse(&data.fields, self.changes)?;
+
+                // A unit struct grows into whatever shape the changes are written in.
+                match (&data.fields, &changes) {
+                    (syn::Fields::Unit, FieldsChange::Named(_)) => {
+                        data.fields = syn::Fields::Named(syn::FieldsNamed {
+                            brace_token: Default::default(),
+                            named: Default::default(),
+                        });
+                        data.semi_token = None;
+                    },
+                    (syn::Fields::Unit, FieldsChange::Unnamed(_)) => {
+                        data.fields = syn::Fields::Unnamed(syn::FieldsUnnamed {
+                            paren_token: Default::default(),
+                            unnamed: Default::default(),
+                        });
+                    },
+                    _ => {},
+                }
+
+                let mut mappings = match (&mut data.fields, &changes) {
+                    (syn::Fields::Named(fields_named), FieldsChange::Named(changes)) => changes.apply(fields_named)?,
+                    (syn::Fields::Unnamed(fields_unnamed), FieldsChange::Unnamed(changes)) => changes.apply(fields_unnamed)?,
+                    (syn::Fields::Named(fields_named), FieldsChange::ToUnnamed(changes)) => {
+                        let (fields, mappings) = changes.apply(fields_named)?;
+                        data.fields = syn::Fields::Unnamed(fields);
+                        data.semi_token = Some(Default::default());
+                        mappings
+                    },
+                    (syn::Fields::Unnamed(fields_unnamed), FieldsChange::ToNamed(changes)) => {
+                        let (fields, mappings) = changes.apply(fields_unnamed)?;
+                        data.fields = syn::Fields::Named(fields);
+                        data.semi_token = None;
+                        mappings
+                    },
+                    (syn::Fields::Unit, FieldsChange::Unit) => Vec::new(),
+                    (_, FieldsChange::Unit) => {
+                        return Err(syn::Error::new_spanned(input, "expected changes to the fields, remove every field to derive a unit struct"))?
+                    },
+                    _ => unreachable!(),
                 };
 
-                self.changes.transform(fields_named)?;
+                if let Some(order) = Directive::order(&self.directives) {
+                    data.fields = order::fields(order, &mut mappings)?;
+                }
+
+                // Removing every field leaves a unit struct.
+                if data.fields.is_empty() && !mappings.is_empty() {
+                    data.fields = syn::Fields::Unit;
+                    data.semi_token = Some(Default::default());
+                }
+
+                mappings
             }
             _ => return Err(syn::Error::new_spanned(input, "only structs are supported"))?,
+        };
+
+        let data = crate::unwrap_one_variant!(&mut input.data, Data::Struct(data), data);
+        let mut replacements = Replacements::new(&self.directives);
+        let targets = mappings.iter_mut().filter(|mapping| mapping.target.is_some());
+        for (field, mapping) in data.fields.iter_mut().zip(targets) {
+            replacements.field(field, mapping);
         }
+        replacements.finish()?;
+
+        generic::substitute(&substitutions, &mut input);
+
+        let data = crate::unwrap_one_variant!(&mut input.data, Data::Struct(data), data);
+        let borrow = Directive::borrow(&self.directives);
+        generic::infer_lifetimes(&mut input.generics, &source.generics, &removed, borrow, data.fields.iter_mut());
+        generic::check_removed(&source.generics, &removed, data.fields.iter())?;
+        let unused = generic::unused(&input.generics, data.fields.iter());
+        let phantom = self.directives.iter().any(|directive| matches!(directive, Directive::Phantom(_)));
+        if phantom && !unused.is_empty() {
+            add_phantom(data, &input.vis, &mut mappings, &unused);
+        } else {
+            generic::prune(&mut input.generics, &unused.iter().map(generic::param_ident).cloned().collect::<Vec<_>>());
+        }
+
+        let fields = |input: &DeriveInput| crate::unwrap_one_variant!(&input.data, Data::Struct(data), data.fields.clone());
+        let (source_fields, target_fields) = (fields(&source), fields(&input));
+        let newtypes = Directive::newtypes(&self.directives);
+        let mut impls = Vec::new();
+        for directive in &self.directives {
+            match directive {
+                Directive::From(_, borrow) => {
+                    let options = Options { borrow: borrow.as_ref(), substitutions: &substitutions, newtypes: &newtypes, ..Options::default() };
+                    let body = conversion::construct(quote! { Self }, &target_fields, &mappings, conversion::source_place, options)?;
+                    impls.push(conversion::from_impl(&source, &input, options, body));
+                },
+                Directive::Reverse(_) => {
+                    let options = Options { substitutions: &substitutions, newtypes: &newtypes, ..Options::default() };
+                    let mappings = conversion::reverse(&mappings)?;
+                    let body = conversion::construct(quote! { Self }, &source_fields, &mappings, conversion::source_place, options)?;
+                    impls.push(conversion::from_impl(&input, &source, options, body));
+                },
+                Directive::TryFrom(_, borrow) => {
+                    let error = conversion::error_ident(&input.ident);
+                    let options = Options { error: Some(&error), borrow: borrow.as_ref(), substitutions: &substitutions, newtypes: &newtypes };
+                    let body = conversion::construct(quote! { Self }, &target_fields, &mappings, conversion::source_place, options)?;
+                    let (definition, error) = conversion::error_enum(&source, &input, &error, &mappings, options);
+                    impls.push(definition);
+                    impls.push(conversion::try_from_impl(&source, &input, error, options, body));
+                },
+                Directive::Inherit(_) | Directive::Order(..) | Directive::Phantom(_) | Directive::Replace(..)
+                | Directive::Newtype(..) => {},
+            }
+        }
+
+        Ok(quote! {
+            #input
+            #(#impls)*
+        })
+    }
+}
+
+/// Adds a `PhantomData` field keeping the generic parameters `params` in use, turning a unit struct into a tuple struct.
+fn add_phantom(data: &mut syn::DataStruct, vis: &syn::Visibility, mappings: &mut Vec<FieldMapping>, params: &[syn::GenericParam]) {
+    if let syn::Fields::Unit = data.fields {
+        data.fields = syn::Fields::Unnamed(syn::FieldsUnnamed {
+            paren_token: Default::default(),
+            unnamed: Default::default(),
+        });
+    }
 
-        Ok(input)
+    let field = syn::Field {
+        attrs: Vec::new(),
+        vis: vis.clone(),
+        mutability: syn::FieldMutability::None,
+        ident: matches!(data.fields, syn::Fields::Named(_)).then(|| quote::format_ident!("_phantom")),
+        colon_token: None,
+        ty: generic::phantom(params),
+    };
+    match &mut data.fields {
+        syn::Fields::Named(fields) => fields.named.push(field.clone()),
+        syn::Fields::Unnamed(fields) => fields.unnamed.push(field.clone()),
+        syn::Fields::Unit => unreachable!(),
     }
+    mappings.push(FieldMapping {
+        source: None,
+        target: Some(field),
+        value: FieldValue::Added {
+            span: proc_macro2::Span::call_site(),
+            init: Some(syn::parse_quote! { ::core::marker::PhantomData }),
+        },
+    });
 }
@@ -1,8 +1,11 @@
 use std::vec::IntoIter;
 
 use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
 
+use crate::conversion::{FieldMapping, FieldValue};
 use crate::named_field_change::NamedFieldChange;
+use crate::selector::FieldSelector;
 use crate::transformer::Transformer;
 
 pub struct StructChange {
@@ -28,6 +31,20 @@ impl Transformer for StructChange {
     }
 
     fn transform(&self, item: &mut Self::Item) -> syn::Result<bool> {
+        self.apply(item)?;
+
+        Ok(true)
+    }
+
+    fn remove(&self, _: &Self::Item) -> syn::Result<bool> {
+        Ok(false)
+    }
+}
+
+impl StructChange {
+    /// Applies the changes to `item` and returns how every field of the source
+    /// and the result relate to each other.
+    pub fn apply(&self, item: &mut syn::FieldsNamed) -> syn::Result<Vec<FieldMapping>> {
         #[derive(Copy, Clone, PartialEq)]
         enum FieldChange {
             Original,
@@ -40,15 +57,40 @@ impl Transformer for StructChange {
             .cloned()
             .map(|field| (field, FieldChange::Original))
             .collect::<Vec<_>>();
+        let mut values = item.named.iter()
+            .cloned()
+            .enumerate()
+            .map(|(index, field)| (Some((index, field)), FieldValue::Moved))
+            .collect::<Vec<_>>();
+
+        // Patterns only apply to the fields which no other change mentions.
+        let changes = self.changes.iter()
+            .filter(|change| !change.is_pattern())
+            .chain(self.changes.iter().filter(|change| change.is_pattern()));
+        for field_change in changes {
+            let names = new_fields.iter()
+                .filter_map(|(field, _)| field.ident.as_ref())
+                .collect::<Vec<_>>();
+            if let Some(ident) = field_change.selector().and_then(|selector| selector.missing(&names)) {
+                return Err(syn::Error::new_spanned(ident, "No field with this name"));
+            }
 
-        for field_change in &self.changes {
             let mut applied = false;
             for variant in field_change.create()? {
-                new_fields.push((variant, FieldChange::Added));
+                let index = match field_change.position() {
+                    Some(position) => position.index(new_fields.iter().map(|(field, _)| field.ident.as_ref()))?,
+                    None => new_fields.len(),
+                };
+                new_fields.insert(index, (variant, FieldChange::Added));
+                values.insert(index, (None, field_change.value()));
                 applied = true;
             }
 
-            for (field, change) in &mut new_fields {
+            for ((field, change), (_, value)) in new_fields.iter_mut().zip(values.iter_mut()) {
+                if field_change.is_pattern() && change != &FieldChange::Original {
+                    continue;
+                }
+
                 let do_remove = field_change.remove(field)?;
                 if do_remove && change != &FieldChange::Original {
                     // Already changed
@@ -74,14 +116,30 @@ impl Transformer for StructChange {
                         ));
                     } else {
                         *change = FieldChange::Changed;
+                        *value = field_change.value();
                         applied = true;
                     }
                 } else if do_remove {
                     *change = FieldChange::Removed;
+                    *value = field_change.value();
                     applied = true;
                 }
             }
 
+            if !applied && field_change.is_pattern() {
+                return Err(syn::Error::new(
+                    field_change.span(),
+                    "No field matches this pattern"
+                ));
+            }
+            if let (false, Some(FieldSelector::Ident(ident))) = (applied, field_change.selector()) {
+                if field_change.is_pick() {
+                    return Err(syn::Error::new_spanned(
+                        ident,
+                        format!("Cannot pick field `{}`, the source has no field with this name", ident)
+                    ));
+                }
+            }
             if !applied {
                 return Err(syn::Error::new(
                     field_change.span(),
@@ -90,6 +148,28 @@ impl Transformer for StructChange {
             }
         }
 
+        // Once a field is picked, only the mentioned fields survive.
+        if self.changes.iter().any(NamedFieldChange::is_pick) {
+            for ((field, change), (_, value)) in new_fields.iter_mut().zip(values.iter_mut()) {
+                if change == &FieldChange::Original {
+                    *change = FieldChange::Removed;
+                    *value = FieldValue::Removed { span: field.span(), default: None };
+                }
+            }
+        }
+
+        let mappings = new_fields.iter()
+            .zip(values)
+            .map(|((field, change), (source, value))| FieldMapping {
+                source,
+                target: match change {
+                    FieldChange::Removed => None,
+                    _ => Some(field.clone()),
+                },
+                value,
+            })
+            .collect();
+
         item.named = new_fields.into_iter()
             .filter_map(|field| match field {
                 (field, FieldChange::Original) => Some(field),
@@ -99,10 +179,6 @@ impl Transformer for StructChange {
             })
             .collect();
 
-        Ok(true)
-    }
-
-    fn remove(&self, _: &Self::Item) -> syn::Result<bool> {
-        Ok(false)
+        Ok(mappings)
     }
 }
@@ -0,0 +1,21 @@
+use syn::visit_mut::VisitMut;
+
+/// Expands a type template, where `_` stands for the original type, e.g. `Option<_>` or `&'a _`.
+/// Types without a `_` are returned as is.
+pub fn expand(template: &syn::Type, original: &syn::Type) -> syn::Type {
+    struct Expand<'a>(&'a syn::Type);
+
+    impl VisitMut for Expand<'_> {
+        fn visit_type_mut(&mut self, ty: &mut syn::Type) {
+            if let syn::Type::Infer(_) = ty {
+                *ty = self.0.clone();
+            } else {
+                syn::visit_mut::visit_type_mut(self, ty);
+            }
+        }
+    }
+
+    let mut ty = template.clone();
+    Expand(original).visit_type_mut(&mut ty);
+    ty
+}
@@ -1,5 +1,9 @@
 use quote::ToTokens;
 use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
+
+use crate::conversion::{FieldMapping, FieldValue};
+use crate::template;
 use crate::transformer::Transformer;
 use crate::unnamed_field_change::UnnamedFieldChange;
 
@@ -20,6 +24,17 @@ impl Parse for TupleChange {
     }
 }
 
+impl TupleChange {
+    /// Parses `( ... )` as a list of changes addressed by position, each parsed with `parse`.
+    pub fn parse_indexed(input: ParseStream, parse: fn(ParseStream) -> syn::Result<UnnamedFieldChange>) -> syn::Result<Self> {
+        let content;
+        syn::parenthesized!(content in input);
+        let changes = content.parse_terminated(parse, syn::Token![,])?;
+        let changes = changes.into_iter().collect();
+        Ok(TupleChange { changes })
+    }
+}
+
 impl Transformer for TupleChange {
     type Item = syn::FieldsUnnamed;
     type CreateIter = std::option::IntoIter<Self::Item>;
@@ -29,33 +44,65 @@ impl Transformer for TupleChange {
     }
 
     fn transform(&self, item: &mut Self::Item) -> syn::Result<bool> {
-        let mut new_fields = Vec::new();
-        let mut old_fields = item.unnamed.iter();
-        let mut changes = self.changes.iter();
-        while let Some(change) = changes.next() {
+        self.apply(item)?;
+
+        Ok(true)
+    }
+
+    fn remove(&self, _: &Self::Item) -> syn::Result<bool> {
+        Ok(false)
+    }
+}
+
+impl TupleChange {
+    /// Applies the changes to `item` and returns how every field of the source
+    /// and the result relate to each other.
+    pub fn apply(&self, item: &mut syn::FieldsUnnamed) -> syn::Result<Vec<FieldMapping>> {
+        let mut mappings = Vec::new();
+        let mut old_fields = item.unnamed.iter().enumerate();
+        let mut rest = None;
+        for change in self.changes.iter().filter(|change| !change.is_indexed()) {
+            if let Some(rest) = rest {
+                return Err(syn::Error::new_spanned(
+                    rest,
+                    "Expected `..` after every change by order",
+                ));
+            }
+
             match change {
-                UnnamedFieldChange::Add { visibility, ty } => {
-                    new_fields.push(syn::Field {
-                        attrs: Vec::new(),
+                UnnamedFieldChange::Add { attrs, visibility, ty, init } => {
+                    let field = syn::Field {
+                        attrs: attrs.clone(),
                         vis: visibility.clone(),
                         ty: ty.clone(),
                         ident: None,
                         mutability: syn::FieldMutability::None,
                         colon_token: None,
+                    };
+                    mappings.push(FieldMapping {
+                        source: None,
+                        target: Some(field),
+                        value: FieldValue::Added { span: ty.span(), init: init.clone() },
                     });
                 }
-                UnnamedFieldChange::Remove { ty } => {
-                    if let Some(ty) = ty {
-                        if let Some(field) = old_fields.next() {
+                UnnamedFieldChange::Remove { ty, default } => {
+                    if let Some((index, field)) = old_fields.next() {
+                        if let Some(ty) = ty {
                             if field.ty.to_token_stream().to_string() != ty.to_token_stream().to_string() {
                                 return Err(syn::Error::new_spanned(
                                     field,
                                     "Expected field to be removed but type did not match",
                                 ));
-                            } else {
-                                continue;
                             }
                         }
+
+                        let span = ty.as_ref().map_or_else(|| field.span(), |ty| ty.span());
+                        mappings.push(FieldMapping {
+                            source: Some((index, field.clone())),
+                            target: None,
+                            value: FieldValue::Removed { span, default: default.clone() },
+                        });
+                        continue;
                     }
 
                     return Err(syn::Error::new_spanned(
@@ -63,27 +110,38 @@ impl Transformer for TupleChange {
                         "Expected field to be removed but there are no more fields",
                     ));
                 }
-                UnnamedFieldChange::Retype { old_type, new_type } => {
+                UnnamedFieldChange::Retype { old_type, new_type, with } => {
+                    let value = || FieldValue::Retyped { span: new_type.span(), with: with.clone() };
                     if let Some(old_type) = old_type {
-                        if let Some(field) = old_fields.next() {
+                        if let Some((index, field)) = old_fields.next() {
                             if field.ty.to_token_stream().to_string() != old_type.to_token_stream().to_string() {
                                 return Err(syn::Error::new_spanned(
                                     field,
                                     "Expected field to be retyped but original type did not match",
                                 ));
                             } else {
-                                new_fields.push(syn::Field {
-                                    ty: new_type.clone(),
+                                let new_field = syn::Field {
+                                    ty: template::expand(new_type, &field.ty),
                                     ..field.clone()
+                                };
+                                mappings.push(FieldMapping {
+                                    source: Some((index, field.clone())),
+                                    target: Some(new_field),
+                                    value: value(),
                                 });
                                 continue;
                             }
                         }
                     } else {
-                        if let Some(field) = old_fields.next() {
-                            new_fields.push(syn::Field {
-                                ty: new_type.clone(),
+                        if let Some((index, field)) = old_fields.next() {
+                            let new_field = syn::Field {
+                                ty: template::expand(new_type, &field.ty),
                                 ..field.clone()
+                            };
+                            mappings.push(FieldMapping {
+                                source: Some((index, field.clone())),
+                                target: Some(new_field),
+                                value: value(),
                             });
                             continue;
                         }
@@ -95,21 +153,22 @@ impl Transformer for TupleChange {
                     ));
                 }
                 UnnamedFieldChange::Match { ty } => {
-                    if let Some(field) = old_fields.next() {
+                    if let Some((index, field)) = old_fields.next() {
                         if let Some(ty) = ty {
                             if field.ty.to_token_stream().to_string() != ty.to_token_stream().to_string() {
                                 return Err(syn::Error::new_spanned(
                                     field,
                                     "Expected field to match but type did not match",
                                 ));
-                            } else {
-                                new_fields.push(field.clone());
-                                continue;
                             }
-                        } else {
-                            new_fields.push(field.clone());
-                            continue;
                         }
+
+                        mappings.push(FieldMapping {
+                            source: Some((index, field.clone())),
+                            target: Some(field.clone()),
+                            value: FieldValue::Moved,
+                        });
+                        continue;
                     }
 
                     return Err(syn::Error::new_spanned(
@@ -117,22 +176,117 @@ impl Transformer for TupleChange {
                         "Expected field to match but there are no more fields",
                     ));
                 }
+                UnnamedFieldChange::Rest(token) => rest = Some(token),
+                _ => unreachable!(),
+            }
+        }
+
+        // Fields addressed by position imply the rest, which is kept as is.
+        let indexed = self.changes.iter().filter(|change| change.is_indexed()).collect::<Vec<_>>();
+        let mut kept = vec![false; item.unnamed.len()];
+        if rest.is_some() || !indexed.is_empty() {
+            for (index, field) in old_fields.by_ref() {
+                kept[index] = true;
+                mappings.push(FieldMapping {
+                    source: Some((index, field.clone())),
+                    target: Some(field.clone()),
+                    value: FieldValue::Moved,
+                });
             }
         }
 
-        if let Some(field) = old_fields.next() {
+        // Retyping every field comes last, so it only applies to fields no other change mentions.
+        let (all, indexed): (Vec<_>, Vec<_>) = indexed.into_iter()
+            .partition(|change| matches!(change, UnnamedFieldChange::RetypeAll { .. }));
+        for change in indexed.into_iter().chain(all) {
+            match change {
+                UnnamedFieldChange::AddAt { index, attrs, visibility, ty, init } => {
+                    if index.index as usize > item.unnamed.len() {
+                        return Err(syn::Error::new_spanned(index, "No field at this position"));
+                    }
+
+                    let field = syn::Field {
+                        attrs: attrs.clone(),
+                        vis: visibility.clone(),
+                        ty: ty.clone(),
+                        ident: None,
+                        mutability: syn::FieldMutability::None,
+                        colon_token: None,
+                    };
+                    let position = mappings.iter()
+                        .position(|mapping| matches!(&mapping.source, Some((source, _)) if *source >= index.index as usize))
+                        .unwrap_or(mappings.len());
+                    mappings.insert(position, FieldMapping {
+                        source: None,
+                        target: Some(field),
+                        value: FieldValue::Added { span: ty.span(), init: init.clone() },
+                    });
+                }
+                UnnamedFieldChange::RemoveAt { index, default } => {
+                    let mapping = kept_mapping(&mut mappings, &mut kept, item, index)?;
+                    mapping.target = None;
+                    mapping.value = FieldValue::Removed { span: index.span, default: default.clone() };
+                }
+                UnnamedFieldChange::RetypeAt { index, new_type, with } => {
+                    let mapping = kept_mapping(&mut mappings, &mut kept, item, index)?;
+                    if let Some(target) = &mut mapping.target {
+                        target.ty = template::expand(new_type, &target.ty);
+                    }
+                    mapping.value = FieldValue::Retyped { span: new_type.span(), with: with.clone() };
+                }
+                UnnamedFieldChange::RetypeAll { star, new_type, with } => {
+                    let mut applied = false;
+                    for mapping in &mut mappings {
+                        match &mapping.source {
+                            Some((index, _)) if std::mem::replace(&mut kept[*index], false) => {},
+                            _ => continue,
+                        }
+                        if let Some(target) = &mut mapping.target {
+                            target.ty = template::expand(new_type, &target.ty);
+                        }
+                        mapping.value = FieldValue::Retyped { span: new_type.span(), with: with.clone() };
+                        applied = true;
+                    }
+
+                    if !applied {
+                        return Err(syn::Error::new_spanned(star, "No field matches this pattern"));
+                    }
+                }
+                _ => unreachable!(),
+            }
+        }
+
+        if let Some((_, field)) = old_fields.next() {
             return Err(syn::Error::new_spanned(
                 field,
                 "Expected no more fields but there are more fields",
             ));
         }
 
-        item.unnamed = syn::punctuated::Punctuated::from_iter(new_fields);
+        item.unnamed = mappings.iter()
+            .filter_map(|mapping| mapping.target.clone())
+            .collect();
 
-        Ok(true)
+        Ok(mappings)
     }
+}
 
-    fn remove(&self, _: &Self::Item) -> syn::Result<bool> {
-        Ok(false)
+/// Finds the mapping of the field at `index`, which must still be kept as is.
+fn kept_mapping<'a>(
+    mappings: &'a mut [FieldMapping],
+    kept: &mut [bool],
+    item: &syn::FieldsUnnamed,
+    index: &syn::Index,
+) -> syn::Result<&'a mut FieldMapping> {
+    let position = index.index as usize;
+    if position >= item.unnamed.len() {
+        return Err(syn::Error::new_spanned(index, "No field at this position"));
     }
-}
\ No newline at end of file
+    if !std::mem::replace(&mut kept[position], false) {
+        return Err(syn::Error::new_spanned(index, "Cannot change field twice"));
+    }
+
+    Ok(mappings.iter_mut()
+        .find(|mapping| matches!(&mapping.source, Some((source, _)) if *source == position))
+        .expect("kept fields have a mapping"))
+}
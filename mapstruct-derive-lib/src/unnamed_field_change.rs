@@ -1,33 +1,101 @@
 use syn::parse::{Parse, ParseStream};
 
+use crate::converter::Converter;
+
 pub enum UnnamedFieldChange {
     Add {
+        attrs: Vec<syn::Attribute>,
         visibility: syn::Visibility,
         ty: syn::Type,
+        /// The value in generated conversions, given after `=`.
+        init: Option<syn::Expr>,
     },
     Remove {
         ty: Option<syn::Type>,
+        /// The value in reverse conversions, given after `=`.
+        default: Option<syn::Expr>,
     },
     Retype {
         old_type: Option<syn::Type>,
         new_type: syn::Type,
+        with: Option<Box<Converter>>,
     },
     Match {
         ty: Option<syn::Type>,
     },
+    /// Add a field before the source position, written as `+N: Type`.
+    AddAt {
+        index: syn::Index,
+        attrs: Vec<syn::Attribute>,
+        visibility: syn::Visibility,
+        ty: syn::Type,
+        init: Option<syn::Expr>,
+    },
+    /// Remove the field at a source position, written as `-N`.
+    RemoveAt {
+        index: syn::Index,
+        default: Option<syn::Expr>,
+    },
+    /// Retype the field at a source position, written as `N: Type`.
+    RetypeAt {
+        index: syn::Index,
+        new_type: syn::Type,
+        with: Option<Box<Converter>>,
+    },
+    /// Retype every field no other change mentions, written as `*: Type`.
+    RetypeAll {
+        star: syn::Token![*],
+        new_type: syn::Type,
+        with: Option<Box<Converter>>,
+    },
+    /// Keep the remaining fields, written as `..`.
+    Rest(syn::Token![..]),
 }
 
 impl Parse for UnnamedFieldChange {
     fn parse(input: ParseStream) -> syn::Result<Self> {
 
+        if input.peek(syn::Token![+]) && input.peek2(syn::LitInt) {
+            input.parse::<syn::Token![+]>()?;
+            return UnnamedFieldChange::parse_add_at(input);
+        }
+        if input.peek(syn::Token![-]) && input.peek2(syn::LitInt) {
+            input.parse::<syn::Token![-]>()?;
+            return UnnamedFieldChange::parse_remove_at(input);
+        }
+        if input.peek(syn::LitInt) {
+            let index = input.parse()?;
+            input.parse::<syn::Token![:]>()?;
+            return Ok(UnnamedFieldChange::RetypeAt {
+                index,
+                new_type: input.parse()?,
+                with: input.call(Converter::parse_optional)?,
+            });
+        }
+        if input.peek(syn::Token![*]) {
+            let star = input.parse()?;
+            input.parse::<syn::Token![:]>()?;
+            return Ok(UnnamedFieldChange::RetypeAll {
+                star,
+                new_type: input.parse()?,
+                with: input.call(Converter::parse_optional)?,
+            });
+        }
+        if input.peek(syn::Token![..]) {
+            return Ok(UnnamedFieldChange::Rest(input.parse()?));
+        }
+
         if input.peek(syn::Token![+]) {
             input.parse::<syn::Token![+]>()?;
+            let attrs = input.call(syn::Attribute::parse_outer)?;
             let visibility = input.parse()?;
             let ty = input.parse()?;
 
             return Ok(UnnamedFieldChange::Add {
+                attrs,
                 visibility,
                 ty,
+                init: input.call(parse_value)?,
             });
         }
         if input.peek(syn::Token![-]) {
@@ -36,11 +104,13 @@ impl Parse for UnnamedFieldChange {
                 input.parse::<syn::Token![_]>()?;
                 return Ok(UnnamedFieldChange::Remove {
                     ty: None,
+                    default: input.call(parse_value)?,
                 });
             } else {
                 let ty = input.parse()?;
                 return Ok(UnnamedFieldChange::Remove {
                     ty: Some(ty),
+                    default: input.call(parse_value)?,
                 });
             }
         }
@@ -49,6 +119,7 @@ impl Parse for UnnamedFieldChange {
             return Ok(UnnamedFieldChange::Retype {
                 old_type: None,
                 new_type: input.parse()?,
+                with: input.call(Converter::parse_optional)?,
             });
         }
 
@@ -65,6 +136,7 @@ impl Parse for UnnamedFieldChange {
             return Ok(UnnamedFieldChange::Retype {
                 old_type: from,
                 new_type,
+                with: input.call(Converter::parse_optional)?,
             });
         }
 
@@ -73,3 +145,48 @@ impl Parse for UnnamedFieldChange {
         });
     }
 }
+
+impl UnnamedFieldChange {
+    /// Parses `N: [#[attr]] Type [= init]`, the part of an insertion after the `+`.
+    pub fn parse_add_at(input: ParseStream) -> syn::Result<Self> {
+        let index = input.parse()?;
+        input.parse::<syn::Token![:]>()?;
+
+        Ok(UnnamedFieldChange::AddAt {
+            index,
+            attrs: input.call(syn::Attribute::parse_outer)?,
+            visibility: input.parse()?,
+            ty: input.parse()?,
+            init: input.call(parse_value)?,
+        })
+    }
+
+    /// Parses `N [= default]`, the part of a removal after the `-`.
+    pub fn parse_remove_at(input: ParseStream) -> syn::Result<Self> {
+        Ok(UnnamedFieldChange::RemoveAt {
+            index: input.parse()?,
+            default: input.call(parse_value)?,
+        })
+    }
+
+    /// Whether the change addresses its field by position instead of by order.
+    pub fn is_indexed(&self) -> bool {
+        matches!(
+            self,
+            UnnamedFieldChange::AddAt { .. }
+                | UnnamedFieldChange::RemoveAt { .. }
+                | UnnamedFieldChange::RetypeAt { .. }
+                | UnnamedFieldChange::RetypeAll { .. }
+        )
+    }
+}
+
+/// Parses an optional `= <expr>`, the initializer of an added field or the default of a removed one.
+pub fn parse_value(input: ParseStream) -> syn::Result<Option<syn::Expr>> {
+    if !input.peek(syn::Token![=]) {
+        return Ok(None);
+    }
+
+    input.parse::<syn::Token![=]>()?;
+    Ok(Some(input.parse()?))
+}
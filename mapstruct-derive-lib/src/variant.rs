@@ -2,14 +2,18 @@ use syn::Fields;
 use syn::parse::{Parse, ParseStream};
 use syn::spanned::Spanned;
 
+use crate::conversion::{self, VariantValue};
+use crate::order::Position;
 use crate::struct_change::StructChange;
 use crate::transformer::Transformer;
 use crate::tuple_change::TupleChange;
+use crate::unnamed_field_change::UnnamedFieldChange;
 
 /// A change instruction for a variant.
 pub enum VariantChange {
     /// Add a new variant to the enum, indicated by a `+` prefix
-    Add(syn::Variant),
+    /// and optionally placed with `before(A)` or `after(A)`.
+    Add(Option<Position>, syn::Variant),
     /// Remove a variant from the enum, indicated by a `-` prefix
     Remove(syn::Ident),
     /// Rename a variant, indicated by a `~` prefix
@@ -27,15 +31,28 @@ pub enum VariantChange {
 
 impl Parse for VariantChange {
     fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(syn::Token![+]) && input.peek2(syn::Ident) && inserts_fields(input) {
+            input.parse::<syn::Token![+]>()?;
+            let ident = input.parse()?;
+            let changes = TupleChange::parse_indexed(input, UnnamedFieldChange::parse_add_at)?;
+            return Ok(VariantChange::TupleRetype(ident, None, changes));
+        }
+
         if input.peek(syn::Token![+]) {
             input.parse::<syn::Token![+]>()?;
+            let position = input.call(Position::parse_optional)?;
             let variant = input.parse()?;
-            return Ok(VariantChange::Add(variant));
+            return Ok(VariantChange::Add(position, variant));
         }
 
         if input.peek(syn::Token![-]) {
             input.parse::<syn::Token![-]>()?;
             let ident = input.parse()?;
+            if input.peek(syn::token::Paren) {
+                let changes = TupleChange::parse_indexed(input, UnnamedFieldChange::parse_remove_at)?;
+                return Ok(VariantChange::TupleRetype(ident, None, changes));
+            }
+
             return Ok(VariantChange::Remove(ident));
         }
 
@@ -73,23 +90,51 @@ impl Parse for VariantChange {
     }
 }
 
+/// Whether `+B(0: u8)` inserts fields into an existing variant instead of adding a new one.
+fn inserts_fields(input: ParseStream) -> bool {
+    fn inserts(input: ParseStream) -> syn::Result<bool> {
+        let content;
+        input.parse::<syn::Token![+]>()?;
+        input.parse::<syn::Ident>()?;
+        syn::parenthesized!(content in input);
+        Ok(content.peek(syn::LitInt) && content.peek2(syn::Token![:]))
+    }
+
+    inserts(&input.fork()).unwrap_or(false)
+}
+
 impl Transformer for VariantChange {
     type Item = syn::Variant;
     type CreateIter = std::option::IntoIter<syn::Variant>;
 
     fn create(&self) -> syn::Result<Self::CreateIter> {
         match self {
-            VariantChange::Add(variant) => Ok(Some(variant.clone()).into_iter()),
+            VariantChange::Add(_, variant) => Ok(Some(variant.clone()).into_iter()),
             _ => Ok(None.into_iter()),
         }
     }
 
     fn transform(&self, variant: &mut Self::Item) -> syn::Result<bool> {
+        Ok(self.apply(variant)?.is_some())
+    }
+
+    fn remove(&self, variant: &Self::Item) -> syn::Result<bool> {
+        match self {
+            VariantChange::Remove(ident) => Ok(&variant.ident == ident),
+            _ => Ok(false),
+        }
+    }
+}
+
+impl VariantChange {
+    /// Applies the change to `variant` if it matches, returning how the fields of the
+    /// source and the result relate to each other.
+    pub fn apply(&self, variant: &mut syn::Variant) -> syn::Result<Option<VariantValue>> {
         match self {
             VariantChange::Rename(from, to) if &variant.ident == from => {
                 variant.ident = to.clone();
 
-                Ok(true)
+                Ok(Some(VariantValue::Mapped(conversion::unchanged(&variant.fields))))
             }
             VariantChange::TupleRetype(from, to, change) if &variant.ident == from => {
                 match &mut variant.fields {
@@ -104,7 +149,7 @@ impl Transformer for VariantChange {
                             variant.ident = to.clone();
                         }
 
-                        change.transform(fields)
+                        Ok(Some(VariantValue::Mapped(change.apply(fields)?)))
                     }
                     Fields::Unit => {
                         Err(syn::Error::new_spanned(
@@ -121,7 +166,7 @@ impl Transformer for VariantChange {
                             variant.ident = to.clone();
                         }
 
-                        change.transform(fields)
+                        Ok(Some(VariantValue::Mapped(change.apply(fields)?)))
                     }
                     syn::Fields::Unnamed(_) => {
                         Err(syn::Error::new_spanned(
@@ -140,29 +185,28 @@ impl Transformer for VariantChange {
             VariantChange::Replace(Some(from), to) if &variant.ident == from => {
                 *variant = to.clone();
 
-                Ok(true)
+                Ok(Some(VariantValue::Replaced { span: self.span() }))
             }
             VariantChange::Replace(None, to) if &variant.ident == &to.ident => {
                 *variant = to.clone();
 
-                Ok(true)
+                Ok(Some(VariantValue::Replaced { span: self.span() }))
             }
-            _ => Ok(false),
+            _ => Ok(None),
         }
     }
 
-    fn remove(&self, variant: &Self::Item) -> syn::Result<bool> {
+    /// Where an added variant is inserted.
+    pub fn position(&self) -> Option<&Position> {
         match self {
-            VariantChange::Remove(ident) => Ok(&variant.ident == ident),
-            _ => Ok(false),
+            VariantChange::Add(position, _) => position.as_ref(),
+            _ => None,
         }
     }
-}
 
-impl VariantChange {
     pub fn span(&self) -> proc_macro2::Span {
         match self {
-            VariantChange::Add(variant) => variant.span(),
+            VariantChange::Add(_, variant) => variant.span(),
             VariantChange::Remove(ident) => ident.span(),
             VariantChange::Rename(from, _) => from.span(),
             VariantChange::TupleRetype(from, _, _) => from.span(),
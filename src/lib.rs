@@ -6,6 +6,7 @@ mod test {
 
     #[derive(MapStruct)]
     #[mapstruct(
+        from(&'a),
         #[derive(Debug)]
         struct Y<
             +'a,
@@ -13,7 +14,7 @@ mod test {
             ~id -> pub id,
             ~name: &'a str,
             ~some: &'a str,
-            +last_name: &'a str,
+            +last_name: &'a str = src.name.as_str(),
             -height,
         }
     )]
@@ -25,20 +26,614 @@ mod test {
         some: String,
     }
 
-    impl<'a> Into<Y<'a>> for &'a X {
-        fn into(self) -> Y<'a> {
-            Y {
-                id: self.id,
-                name: &*self.name,
-                age: self.age,
-                some: &*self.some,
-                last_name: &*self.name,
+    #[test]
+    fn test() {
+        let x = X {
+            id: 1,
+            name: "name".to_string(),
+            age: 2,
+            height: 3.0,
+            some: "some".to_string(),
+        };
+        let y = Y::from(&x);
+        assert_eq!(x.height, 3.0);
+        assert_eq!((y.id, y.name, y.age, y.some, y.last_name), (1, "name", 2, "some", "name"));
+    }
+
+    #[derive(MapStruct)]
+    #[mapstruct(
+        from,
+        order(label, name, b_id),
+        #[derive(Debug)]
+        struct Ordered {
+            ~id -> b_id,
+            -secret,
+            -created,
+            +before(name) label: &'static str = "a",
+        }
+    )]
+    #[mapstruct(
+        from,
+        struct B {
+            ~id -> b_id,
+            -secret,
+            ~created: u64 with u64::from,
+            +label: String = format!("{}#{}", src.name, src.id),
+        }
+    )]
+    struct A {
+        id: u64,
+        name: String,
+        secret: String,
+        created: u32,
+    }
+
+    #[test]
+    fn test_from() {
+        let a = A {
+            id: 1,
+            name: "name".to_string(),
+            secret: "secret".to_string(),
+            created: 3,
+        };
+        assert_eq!(a.secret, "secret");
+        let ordered = Ordered::from(A { id: 2, name: "n".to_string(), secret: String::new(), created: 0 });
+        assert_eq!(format!("{:?}", ordered), r#"Ordered { label: "a", name: "n", b_id: 2 }"#);
+        assert_eq!((ordered.label, ordered.name.as_str(), ordered.b_id), ("a", "n", 2));
+        let b = B::from(a);
+        assert_eq!((b.b_id, b.name.as_str(), b.label.as_str(), b.created), (1, "name", "name#1", 3u64));
+    }
+
+    #[derive(MapStruct)]
+    #[mapstruct(
+        from,
+        reverse,
+        struct UserDto {
+            ~id -> user_id,
+            -password = String::new(),
+        }
+    )]
+    #[mapstruct(
+        from,
+        struct PublicUser {
+            =id,
+        }
+    )]
+    #[mapstruct(
+        from,
+        struct UserPatch {
+            ~*: Option<_>,
+        }
+    )]
+    struct User {
+        id: u64,
+        password: String,
+    }
+
+    #[test]
+    fn test_reverse() {
+        let user = User {
+            id: 1,
+            password: "secret".to_string(),
+        };
+        let dto = UserDto::from(user);
+        assert_eq!(dto.user_id, 1);
+        let user = User::from(dto);
+        assert_eq!((user.id, user.password.as_str()), (1, ""));
+        let patch = UserPatch::from(User { id: 2, password: "secret".to_string() });
+        assert_eq!((patch.id, patch.password.as_deref()), (Some(2), Some("secret")));
+        assert_eq!(PublicUser::from(user).id, 1);
+    }
+
+    fn parse_email(email: String) -> Result<String, String> {
+        if email.contains('@') {
+            Ok(email)
+        } else {
+            Err(email)
+        }
+    }
+
+    #[derive(MapStruct)]
+    #[mapstruct(
+        try_from,
+        #[derive(Debug)]
+        struct Narrow {
+            ~age: u8,
+            ~email: String try with parse_email: String,
+        }
+    )]
+    struct Wide {
+        age: i64,
+        email: String,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct UserId(u32);
+
+    #[derive(MapStruct)]
+    #[mapstruct(
+        from,
+        newtype UserId(u32),
+        struct RawUser {
+            ~id: u32,
+            ~age: Option<u32>,
+        }
+    )]
+    struct Member {
+        id: UserId,
+        age: u32,
+    }
+
+    #[test]
+    fn test_newtype() {
+        let raw = RawUser::from(Member { id: UserId(7), age: 3 });
+        assert_eq!((raw.id, raw.age), (7, Some(3)));
+    }
+
+    mod infallible {
+        #![allow(clippy::infallible_try_from)]
+        use super::*;
+
+        #[derive(MapStruct)]
+        #[mapstruct(
+            try_from,
+            pub struct Untouched {
+                -email,
             }
+        )]
+        pub struct Wide {
+            pub age: i64,
+            pub email: String,
         }
     }
 
+    #[derive(MapStruct)]
+    #[mapstruct(
+        try_from,
+        #[derive(Debug)]
+        struct Counted {
+            ~count: u8 try with u8::try_from: <u8 as TryFrom<T>>::Error,
+        }
+    )]
+    struct Counter<T> where u8: TryFrom<T> {
+        count: T,
+    }
+
     #[test]
-    fn test() {
-        println!("Hello, world!")
+    fn test_try_from() {
+        let narrow = Narrow::try_from(Wide { age: 42, email: "a@b".to_string() }).unwrap();
+        assert_eq!((narrow.age, narrow.email.as_str()), (42, "a@b"));
+        let error = Narrow::try_from(Wide { age: 300, email: "a@b".to_string() }).unwrap_err();
+        assert!(matches!(error, NarrowConversionError::Age(_)));
+        let error = Narrow::try_from(Wide { age: 1, email: "ab".to_string() }).unwrap_err();
+        assert_eq!(error.to_string(), "failed to convert field `email`: ab");
+        let wide = infallible::Wide { age: 300, email: "ab".to_string() };
+        assert_eq!(wide.email, "ab");
+        let untouched = infallible::Untouched::try_from(wide).unwrap();
+        assert_eq!(untouched.age, 300);
+
+        assert_eq!(Counted::try_from(Counter { count: 7u16 }).unwrap().count, 7);
+        let error: Box<dyn std::error::Error> = Box::new(Counted::try_from(Counter { count: 300u16 }).unwrap_err());
+        assert_eq!(error.to_string(), "failed to convert field `count`: out of range integral type conversion attempted");
+    }
+
+    #[derive(MapStruct)]
+    #[mapstruct(
+        try_from,
+        reverse,
+        #[derive(Debug, PartialEq)]
+        enum Subset {
+            -Circle,
+            ~Square -> Quad,
+            ~Rect {
+                -label = String::from("rect"),
+            },
+        }
+    )]
+    #[mapstruct(
+        from(&'a),
+        #[derive(Debug, PartialEq)]
+        enum Borrowed<
+            +'a,
+        > {
+            ~Circle(~&'a f64),
+            ~Rect {
+                ~label: &'a str,
+            },
+            +Empty,
+        }
+    )]
+    #[derive(Debug, PartialEq)]
+    enum Shape {
+        Circle(f64),
+        Square(f64),
+        Rect {
+            width: f64,
+            label: String,
+        },
+    }
+
+    #[test]
+    fn test_enum_conversions() {
+        assert_eq!(Subset::try_from(Shape::Square(1.0)), Ok(Subset::Quad(1.0)));
+        assert_eq!(Subset::try_from(Shape::Circle(1.0)), Err(Shape::Circle(1.0)));
+        assert_eq!(
+            Shape::from(Subset::Rect { width: 2.0 }),
+            Shape::Rect { width: 2.0, label: "rect".to_string() },
+        );
+
+        let shape = Shape::Rect { width: 2.0, label: "label".to_string() };
+        assert_eq!(Borrowed::from(&shape), Borrowed::Rect { width: 2.0, label: "label" });
+        assert_eq!(Borrowed::from(&Shape::Circle(1.0)), Borrowed::Circle(&1.0));
+        assert_ne!(Borrowed::from(&Shape::Square(1.0)), Borrowed::Empty);
+    }
+
+    #[derive(MapStruct)]
+    #[mapstruct(
+        from,
+        reverse,
+        #[derive(Debug, PartialEq)]
+        struct Meters(_, -String = String::from("m"), +bool = true);
+    )]
+    #[mapstruct(
+        inherit,
+        -#[derive(PartialEq)]
+        struct Kilometers(_, -String);
+    )]
+    #[derive(Debug, PartialEq)]
+    struct Distance(f64, String);
+
+    #[test]
+    fn test_tuple_struct() {
+        let meters = Meters::from(Distance(1.0, "km".to_string()));
+        assert_eq!(meters, Meters(1.0, true));
+        assert_eq!(Distance::from(meters), Distance(1.0, "m".to_string()));
+        let kilometers = Kilometers(1.0);
+        assert_eq!((format!("{:?}", kilometers), kilometers.0), ("Kilometers(1.0)".to_string(), 1.0));
+    }
+
+    #[derive(MapStruct)]
+    #[mapstruct(
+        from,
+        inherit,
+        +#[derive(Hash)]
+        -#[derive(Copy)]
+        -#[repr]
+        struct PublicRecord {
+            -secret,
+        }
+    )]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[repr(C)]
+    /// A record with a secret.
+    struct Record {
+        id: u64,
+        secret: u64,
+    }
+
+    #[derive(Debug, Clone, MapStruct)]
+    #[mapstruct(
+        from,
+        inherit,
+        #[derive(Debug)]
+        struct SharedRecord {}
+    )]
+    #[derive(PartialEq)]
+    struct LocalRecord {
+        id: u64,
+    }
+
+    #[test]
+    fn test_inherit() {
+        let record = Record { id: 1, secret: 2 };
+        assert_eq!(record.secret, 2);
+        let public = PublicRecord::from(record);
+        assert_eq!(public.clone(), PublicRecord { id: 1 });
+        let mut set = std::collections::HashSet::new();
+        set.insert(public);
+
+        // The derives next to `MapStruct` are not inherited, only the ones below it.
+        let shared = SharedRecord::from(LocalRecord { id: 3 }.clone());
+        assert_eq!(format!("{:?}", shared), "SharedRecord { id: 3 }");
+        assert!(shared == SharedRecord { id: 3 });
+    }
+
+    #[derive(MapStruct)]
+    #[mapstruct(
+        from,
+        reverse,
+        #[derive(Debug, PartialEq)]
+        struct Point(x, y);
+    )]
+    #[derive(Debug, PartialEq)]
+    struct Coordinates {
+        x: f32,
+        y: f32,
+    }
+
+    #[derive(MapStruct)]
+    #[mapstruct(
+        from,
+        reverse,
+        #[derive(Debug, PartialEq)]
+        struct Position {
+            1 -> pub top,
+            0 -> pub left: f64 with f64::from back |left| left as f32,
+        }
+    )]
+    #[mapstruct(
+        from,
+        #[derive(Debug, PartialEq)]
+        struct Widened(-_, 1: f64 with f64::from, +2: bool = true);
+    )]
+    #[derive(Debug, PartialEq)]
+    struct Pair(f32, f32);
+
+    #[test]
+    fn test_shape_change() {
+        let point = Point::from(Coordinates { x: 1.0, y: 2.0 });
+        assert_eq!(point, Point(1.0, 2.0));
+        assert_eq!(Coordinates::from(point), Coordinates { x: 1.0, y: 2.0 });
+        assert_eq!(Position::from(Pair(1.0, 2.0)), Position { top: 2.0, left: 1.0 });
+        assert_eq!(Pair::from(Position { top: 2.0, left: 1.0 }), Pair(1.0, 2.0));
+    }
+
+    #[test]
+    fn test_indexed_tuple_changes() {
+        assert_eq!(Widened::from(Pair(1.0, 2.0)), Widened(2.0, true));
+    }
+
+    #[derive(MapStruct)]
+    #[mapstruct(
+        from,
+        #[derive(Debug, PartialEq)]
+        struct Shared<
+            ~T: Clone + Send,
+            ~const N: usize = 2,
+        > {}
+    )]
+    #[derive(Debug, PartialEq)]
+    struct Local<T: Clone, const N: usize> {
+        items: [T; N],
+    }
+
+    #[test]
+    fn test_changed_generics() {
+        let shared: Shared<u8> = Shared::from(Local { items: [1, 2] });
+        assert_eq!(shared, Shared { items: [1, 2] });
+    }
+
+    #[derive(MapStruct)]
+    #[mapstruct(
+        from,
+        #[derive(Debug, PartialEq)]
+        enum Redacted {
+            ~Login {
+                -secret,
+                +has_secret: bool = !secret.is_empty(),
+            },
+        }
+    )]
+    enum Event {
+        Login {
+            user: u32,
+            secret: String,
+        },
+        Logout,
+    }
+
+    #[test]
+    fn test_variant_initializer_reads_removed_field() {
+        let event = Event::Login { user: 1, secret: "x".to_string() };
+        assert_eq!(Redacted::from(event), Redacted::Login { user: 1, has_secret: true });
+        assert_eq!(Redacted::from(Event::Logout), Redacted::Logout);
+    }
+
+    #[derive(MapStruct)]
+    #[mapstruct(
+        from,
+        reverse,
+        #[derive(Debug, PartialEq)]
+        enum Marked<
+            ~T -> Label,
+        > {}
+    )]
+    #[mapstruct(
+        from,
+        reverse,
+        #[derive(Debug, PartialEq)]
+        enum Labelled<
+            ~T => String,
+        > {}
+    )]
+    #[derive(Debug, PartialEq)]
+    enum Tagged<T> {
+        Single(T),
+        Pair {
+            left: T,
+            right: u8,
+        },
+    }
+
+    #[test]
+    fn test_substituted_generics() {
+        let labelled = Labelled::from(Tagged::Single("a".to_string()));
+        assert_eq!(labelled, Labelled::Single("a".to_string()));
+        assert_eq!(
+            Tagged::from(Labelled::Pair { left: "b".to_string(), right: 1 }),
+            Tagged::Pair { left: "b".to_string(), right: 1 },
+        );
+        assert_eq!(Marked::from(Tagged::Single(1)), Marked::<i32>::Single(1));
+        assert_eq!(Tagged::from(Marked::Single(2)), Tagged::Single(2));
+    }
+
+    #[derive(MapStruct)]
+    #[mapstruct(
+        from,
+        #[derive(Debug, PartialEq)]
+        struct Optional<
+            ~T => String,
+        > {
+            ~value: Option<String>,
+        }
+    )]
+    #[mapstruct(
+        try_from,
+        #[derive(Debug, PartialEq)]
+        struct Byte<
+            ~T => u32,
+        > {
+            ~value: u8,
+        }
+    )]
+    #[mapstruct(
+        from,
+        #[derive(Debug, PartialEq)]
+        struct Named<
+            ~T -> Item,
+        > {
+            ~value: Option<Item>,
+        }
+    )]
+    struct Wrapper<T> {
+        value: T,
+    }
+
+    #[test]
+    fn test_substituted_retyped_fields() {
+        let optional = Optional::from(Wrapper { value: "a".to_string() });
+        assert_eq!(optional, Optional { value: Some("a".to_string()) });
+        assert_eq!(Byte::try_from(Wrapper { value: 7u32 }).unwrap(), Byte { value: 7 });
+        assert!(matches!(Byte::try_from(Wrapper { value: 300u32 }), Err(ByteConversionError::Value(_))));
+        assert_eq!(Named::from(Wrapper { value: 1 }), Named { value: Some(1) });
+    }
+
+    #[derive(MapStruct)]
+    #[mapstruct(
+        from,
+        reverse,
+        phantom,
+        #[derive(Debug, PartialEq)]
+        struct TypedId {
+            -value = None,
+        }
+    )]
+    #[mapstruct(
+        from,
+        #[derive(Debug, PartialEq)]
+        struct Id {
+            -value,
+        }
+    )]
+    #[mapstruct(
+        from(&'a),
+        struct TypedView<
+            +'a,
+        > {
+            ~value: &'a Option<T>,
+        }
+    )]
+    #[derive(Debug, PartialEq)]
+    struct Typed<T> {
+        id: u64,
+        value: Option<T>,
+    }
+
+    #[test]
+    fn test_unused_generics() {
+        let typed_id = TypedId::<String>::from(Typed { id: 1, value: None });
+        assert_eq!(typed_id, TypedId { id: 1, _phantom: core::marker::PhantomData });
+        assert_eq!(Typed::from(typed_id), Typed { id: 1, value: None });
+        assert_eq!(Id::from(Typed { id: 2, value: Some(1) }), Id { id: 2 });
+        let typed = Typed { id: 3, value: Some(2) };
+        let view = TypedView::from(&typed);
+        assert_eq!((view.id, view.value), (3, &Some(2)));
+    }
+
+    #[derive(MapStruct)]
+    #[mapstruct(
+        from,
+        reverse,
+        phantom,
+        #[derive(Debug, Clone, PartialEq)]
+        enum Signal {
+            ~Value(_, -Option<T> = None),
+        }
+    )]
+    #[derive(Debug, PartialEq)]
+    enum Message<T> {
+        Ping,
+        Value(u8, Option<T>),
+    }
+
+    #[test]
+    fn test_unused_enum_generics() {
+        let signal = Signal::<String>::from(Message::Value(1, Some("a".to_string())));
+        assert_eq!(signal.clone(), Signal::Value(1));
+        assert_eq!(Message::from(signal), Message::Value(1, None));
+        assert_eq!(Message::<String>::from(Signal::Ping), Message::Ping);
+    }
+
+    #[derive(MapStruct)]
+    #[mapstruct(
+        from(&'a),
+        #[derive(Debug, PartialEq)]
+        struct AccountView {
+            ~owner: &str,
+            +kind: &'static str = "savings",
+        }
+    )]
+    struct Account {
+        owner: String,
+        balance: i64,
+    }
+
+    #[test]
+    fn test_inferred_lifetimes() {
+        let account = Account { owner: "owner".to_string(), balance: 1 };
+        assert_eq!(AccountView::from(&account), AccountView { owner: "owner", balance: 1, kind: "savings" });
+    }
+
+    #[derive(MapStruct)]
+    #[mapstruct(
+        from,
+        replace String => std::sync::Arc<str> with std::sync::Arc::from,
+        #[derive(Debug, PartialEq)]
+        struct SharedTicket {}
+    )]
+    struct Ticket {
+        title: String,
+        assignee: String,
+        points: u8,
+    }
+
+    #[test]
+    fn test_replace() {
+        let ticket = SharedTicket::from(Ticket { title: "title".to_string(), assignee: "me".to_string(), points: 3 });
+        assert_eq!(ticket, SharedTicket { title: "title".into(), assignee: "me".into(), points: 3 });
+    }
+
+    #[derive(MapStruct)]
+    #[mapstruct(
+        from,
+        #[derive(Debug, PartialEq)]
+        struct Locked;
+    )]
+    #[mapstruct(
+        from,
+        reverse,
+        #[derive(Debug, PartialEq)]
+        struct Opened {
+            +since: u64 = 1,
+        }
+    )]
+    #[derive(Debug, PartialEq)]
+    struct Unlocked;
+
+    #[test]
+    fn test_unit_struct() {
+        assert_eq!(Locked::from(Unlocked), Locked);
+        assert_eq!(Opened::from(Unlocked), Opened { since: 1 });
+        assert_eq!(Unlocked::from(Opened { since: 2 }), Unlocked);
     }
 }